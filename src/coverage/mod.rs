@@ -0,0 +1,215 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use crate::{
+    interpreter::{
+        hook::Hook, runtime_error::RuntimeError, script_statements::ScriptStatements, Interpreter,
+    },
+    parser::{expression::Expression, statement::Statement},
+    token::Token,
+    visitor::{expression_visitor::ExpressionVisitor, statement_visitor::StatementVisitor},
+};
+
+// an if, while or logical operator, with how often each side was taken
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Branch {
+    line: usize,
+    // where it starts in the source, to order branches on the same line
+    start: usize,
+    taken: [u64; 2],
+}
+
+// Records which lines and branches of a script run. Statements and branch
+// points are found up front and matched by address as they run, so code in
+// imported modules isn't counted against the script.
+pub struct Coverage {
+    file: String,
+    statements: ScriptStatements,
+    lines: BTreeMap<usize, u64>,
+    // keyed by the address of the keyword or operator
+    branches: HashMap<usize, Branch>,
+}
+
+impl Coverage {
+    pub fn new(file: String, statements: &[Statement]) -> Self {
+        let mut walker = Walker::default();
+        walker.statements(statements);
+
+        let statements = ScriptStatements::new(statements);
+        let lines = statements.lines().map(|line| (line, 0)).collect();
+        Self {
+            file,
+            statements,
+            lines,
+            branches: walker.branches,
+        }
+    }
+
+    // one record for the script, in the lcov tracefile format
+    pub fn write_lcov(&self, output: &mut dyn Write) -> io::Result<()> {
+        writeln!(output, "TN:")?;
+        writeln!(output, "SF:{}", self.file)?;
+
+        let mut branches: Vec<_> = self.branches.values().collect();
+        branches.sort_by_key(|branch| (branch.line, branch.start));
+
+        let mut hit = 0;
+        for (block, branch) in branches.into_iter().enumerate() {
+            let reached = self.lines.get(&branch.line).copied().unwrap_or(0) > 0;
+            for (side, taken) in branch.taken.iter().enumerate() {
+                if *taken > 0 {
+                    hit += 1;
+                }
+                // lcov uses "-" for a branch whose line never ran
+                let taken = if reached {
+                    taken.to_string()
+                } else {
+                    "-".to_string()
+                };
+                writeln!(output, "BRDA:{},{},{},{}", branch.line, block, side, taken)?;
+            }
+        }
+        writeln!(output, "BRF:{}", self.branches.len() * 2)?;
+        writeln!(output, "BRH:{}", hit)?;
+
+        for (line, hits) in &self.lines {
+            writeln!(output, "DA:{},{}", line, hits)?;
+        }
+        writeln!(output, "LF:{}", self.lines.len())?;
+        writeln!(
+            output,
+            "LH:{}",
+            self.lines.values().filter(|&&hits| hits > 0).count()
+        )?;
+        writeln!(output, "end_of_record")
+    }
+}
+
+impl Hook for Coverage {
+    fn before_statement(
+        &mut self,
+        _interpreter: &Interpreter,
+        statement: &Statement,
+    ) -> Result<(), RuntimeError> {
+        if let Some(line) = self.statements.line(statement) {
+            *self.lines.entry(line).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn on_branch(&mut self, _interpreter: &Interpreter, at: &Token, branch: usize) {
+        if let Some(point) = self.branches.get_mut(&(at as *const _ as usize)) {
+            point.taken[branch] += 1;
+        }
+    }
+}
+
+// finds the branch points of a script
+#[derive(Default)]
+struct Walker {
+    branches: HashMap<usize, Branch>,
+}
+
+impl Walker {
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let _ = statement.accept(self);
+        }
+    }
+
+    fn branch(&mut self, at: &Token) {
+        let branch = Branch {
+            line: at.line,
+            start: at.span.start,
+            taken: [0, 0],
+        };
+        self.branches.insert(at as *const _ as usize, branch);
+    }
+}
+
+impl StatementVisitor<(), ()> for Walker {
+    fn visit_expression(&mut self, expression: &Expression) -> Result<(), ()> {
+        expression.accept(self)
+    }
+
+    fn visit_if_statement(
+        &mut self,
+        keyword: &Token,
+        condition: &Expression,
+        then_branch: &Statement,
+        else_branch: &Option<Box<Statement>>,
+    ) -> Result<(), ()> {
+        self.branch(keyword);
+        condition.accept(self)?;
+        then_branch.accept(self)?;
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while_statement(
+        &mut self,
+        keyword: &Token,
+        condition: &Expression,
+        body: &Statement,
+    ) -> Result<(), ()> {
+        self.branch(keyword);
+        condition.accept(self)?;
+        body.accept(self)
+    }
+}
+
+impl ExpressionVisitor<(), ()> for Walker {
+    fn visit_logical(
+        &mut self,
+        left: &Expression,
+        operator: &Token,
+        right: &Expression,
+    ) -> Result<(), ()> {
+        self.branch(operator);
+        left.accept(self)?;
+        right.accept(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_lcov() {
+        let source = "var a = 1;\nif (a > 0) {\n  print a;\n} else {\n  print -a;\n}\nwhile (a < 3) a = a + 1;\nprint a > 1 or a < 0;";
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        let coverage = Rc::new(RefCell::new(Coverage::new(
+            "test.lox".to_string(),
+            &statements,
+        )));
+        Interpreter::new()
+            .with_hook(Box::new(Rc::clone(&coverage)))
+            .interpret(&statements)
+            .unwrap();
+
+        let mut lcov = Vec::new();
+        coverage.borrow().write_lcov(&mut lcov).unwrap();
+        let lcov = String::from_utf8(lcov).unwrap();
+        assert_eq!(
+            lcov,
+            "TN:\nSF:test.lox\n\
+             BRDA:2,0,0,1\nBRDA:2,0,1,0\n\
+             BRDA:7,1,0,2\nBRDA:7,1,1,1\n\
+             BRDA:8,2,0,1\nBRDA:8,2,1,0\n\
+             BRF:6\nBRH:4\n\
+             DA:1,1\nDA:2,1\nDA:3,1\nDA:5,0\nDA:7,3\nDA:8,1\n\
+             LF:6\nLH:5\nend_of_record\n"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::Write,
+};
+
+use crate::{
+    interpreter::{hook::Hook, runtime_error::RuntimeError, Input, Interpreter},
+    parser::statement::Statement,
+    token::Token,
+};
+
+const HELP: &str = "\
+break <line>, b    stop when the line is reached
+delete <line>, d   remove a breakpoint
+continue, c        run to the next breakpoint
+step, s            run to the next statement, stepping into calls
+next, n            run to the next statement in this function or its caller
+out, o             run until the current function returns
+backtrace, bt      show the call stack
+env, e             show the variables in scope, innermost scope first
+print <name>, p    show the value of a variable
+list, l            show the source around the current line
+quit, q            stop the program";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Continue,
+    StepInto,
+    // the call depth to stop at or above
+    StepOver(usize),
+    StepOut(usize),
+    // the input has closed, so run to the end without stopping
+    Detached,
+    // stop the program the way exit(0) does
+    Quit,
+}
+
+// A command line debugger, run as a hook before every statement. It starts
+// paused on the first statement and reads commands until one resumes the
+// program.
+pub struct Debugger {
+    lines: Vec<String>,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    // the line and depth of the statement before, and the statements run
+    // since reaching it, so several statements on one line only stop once but
+    // a loop on one line stops on every pass
+    last: Option<(usize, usize)>,
+    on_line: HashSet<usize>,
+    // shared with the interpreter, so read_line gets the lines after a command
+    input: Input,
+    output: Box<dyn Write>,
+}
+
+impl Debugger {
+    pub fn new(source: &str, input: Input, output: Box<dyn Write>) -> Self {
+        Self {
+            lines: source.lines().map(str::to_string).collect(),
+            breakpoints: BTreeSet::new(),
+            mode: Mode::StepInto,
+            last: None,
+            on_line: HashSet::new(),
+            input,
+            output,
+        }
+    }
+
+    fn should_stop(&self, line: usize, depth: usize) -> bool {
+        let breakpoint = self.breakpoints.contains(&line);
+        match self.mode {
+            Mode::Continue => breakpoint,
+            Mode::StepInto => true,
+            Mode::StepOver(stop) => breakpoint || depth <= stop,
+            Mode::StepOut(stop) => breakpoint || depth < stop,
+            Mode::Detached | Mode::Quit => false,
+        }
+    }
+
+    // reads commands until one resumes the program
+    fn pause(&mut self, interpreter: &Interpreter, line: usize) -> std::io::Result<()> {
+        writeln!(
+            self.output,
+            "Stopped at line {}: {}",
+            line,
+            source(&self.lines, line).trim()
+        )?;
+
+        loop {
+            write!(self.output, "(lox) ")?;
+            self.output.flush()?;
+
+            let mut command = String::new();
+            if self.input.borrow_mut().read_line(&mut command)? == 0 {
+                self.mode = Mode::Detached;
+                return Ok(());
+            }
+
+            let mut words = command.split_whitespace();
+            let depth = interpreter.frames().len();
+            match (words.next(), words.next()) {
+                (None, _) => {}
+                (Some("break" | "b"), Some(line)) => match line.parse::<usize>() {
+                    Ok(line) if line > 0 && line <= self.lines.len() => {
+                        self.breakpoints.insert(line);
+                        writeln!(self.output, "Breakpoint at line {}.", line)?;
+                    }
+                    _ => writeln!(self.output, "No line {} in the source.", line)?,
+                },
+                (Some("delete" | "d"), Some(line)) => match line.parse::<usize>() {
+                    Ok(line) if self.breakpoints.remove(&line) => {
+                        writeln!(self.output, "Deleted breakpoint at line {}.", line)?
+                    }
+                    _ => writeln!(self.output, "No breakpoint at line {}.", line)?,
+                },
+                (Some("continue" | "c"), _) => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                (Some("step" | "s"), _) => {
+                    self.mode = Mode::StepInto;
+                    return Ok(());
+                }
+                (Some("next" | "n"), _) => {
+                    self.mode = Mode::StepOver(depth);
+                    return Ok(());
+                }
+                (Some("out" | "o"), _) => {
+                    self.mode = Mode::StepOut(depth);
+                    return Ok(());
+                }
+                (Some("backtrace" | "bt"), _) => {
+                    for (number, frame) in interpreter.frames().iter().rev().enumerate() {
+                        writeln!(
+                            self.output,
+                            "#{} {} at line {}",
+                            number, frame.name, frame.line
+                        )?;
+                    }
+                }
+                (Some("env" | "e"), _) => {
+                    interpreter.environment.borrow().print(&mut self.output)?
+                }
+                (Some("print" | "p"), Some(name)) => {
+                    let token = Token::new_identifier(line, name.to_string());
+                    match interpreter.environment.borrow().get(&token) {
+                        Ok(value) => writeln!(self.output, "{} = {}", name, value)?,
+                        Err(error) => writeln!(self.output, "{}", error)?,
+                    }
+                }
+                (Some("list" | "l"), _) => {
+                    let first = line.saturating_sub(2).max(1);
+                    for number in first..=(line + 2).min(self.lines.len()) {
+                        let marker = if number == line { ">" } else { " " };
+                        writeln!(
+                            self.output,
+                            "{} {:>4} {}",
+                            marker,
+                            number,
+                            source(&self.lines, number)
+                        )?;
+                    }
+                }
+                (Some("quit" | "q"), _) => {
+                    self.mode = Mode::Quit;
+                    return Ok(());
+                }
+                (Some("help" | "h"), _) => writeln!(self.output, "{}", HELP)?,
+                (Some(command), _) => writeln!(
+                    self.output,
+                    "Unknown command: {}. Type help for a list of commands.",
+                    command
+                )?,
+            }
+        }
+    }
+}
+
+// the text of a 1-based line
+fn source(lines: &[String], line: usize) -> &str {
+    lines.get(line.wrapping_sub(1)).map_or("", String::as_str)
+}
+
+impl Hook for Debugger {
+    fn before_statement(
+        &mut self,
+        interpreter: &Interpreter,
+        statement: &Statement,
+    ) -> Result<(), RuntimeError> {
+        let here = (statement.line(), interpreter.frames().len());
+        let key = statement as *const _ as usize;
+        if self.last == Some(here) && self.on_line.insert(key) {
+            return Ok(());
+        }
+        self.last = Some(here);
+        self.on_line.clear();
+        self.on_line.insert(key);
+
+        if self.should_stop(here.0, here.1) && self.pause(interpreter, here.0).is_err() {
+            // with nowhere to read commands from, let the program finish
+            self.mode = Mode::Detached;
+        }
+
+        if self.mode == Mode::Quit {
+            return Err(RuntimeError::Exit(0, statement.span()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser, test_output::Output};
+    use std::{cell::RefCell, io::Cursor, rc::Rc};
+
+    fn debug(source: &str, commands: &str) -> String {
+        let (output, result) = run(source, commands);
+        result.unwrap();
+        output
+    }
+
+    fn run(source: &str, commands: &str) -> (String, Result<(), RuntimeError>) {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        let output = Output::default();
+        let input: Input = Rc::new(RefCell::new(Cursor::new(commands.to_string())));
+        let debugger = Debugger::new(source, Rc::clone(&input), Box::new(output.clone()));
+        let result = Interpreter::new()
+            .with_input(input)
+            .with_hook(Box::new(debugger))
+            .interpret(&statements);
+
+        (output.contents(), result)
+    }
+
+    // the lines the debugger stopped at, in order
+    fn stops(output: &str) -> Vec<&str> {
+        output
+            .split("Stopped at line ")
+            .skip(1)
+            .map(|rest| rest.split(':').next().unwrap())
+            .collect()
+    }
+
+    const SOURCE: &str = "\
+fun f(a) {
+  var b = a + 1;
+  return b;
+}
+var x = f(1);
+var y = f(x);
+print y;";
+
+    #[test]
+    fn test_stepping() {
+        // stops on entry at 1, steps into f from 5, then steps out back to 6
+        let output = debug(SOURCE, "n\ns\ns\no\nc\n");
+        assert_eq!(stops(&output), vec!["1", "5", "2", "3", "6"]);
+
+        // stepping over never stops inside f
+        let output = debug(SOURCE, "n\nn\nn\nn\n");
+        assert_eq!(stops(&output), vec!["1", "5", "6", "7"]);
+    }
+
+    #[test]
+    fn test_inspection() {
+        let output = debug(SOURCE, "b 3\nc\np a\np x\nbt\nc\np b\nc\n");
+        assert!(output.contains("Breakpoint at line 3."));
+        assert!(output.contains("a = 1\n"));
+        assert!(output.contains("Undefined variable 'x'."));
+        assert!(output.contains("#0 <fn f> at line 3\n#1 script at line 5"));
+        assert!(output.contains("b = 3\n"));
+    }
+
+    #[test]
+    fn test_breakpoint_in_one_line_loop() {
+        let source = "var i = 0;\nwhile (i < 3) i = i + 1;\nprint i;";
+        // the first pass of the body is part of reaching the line
+        let output = debug(source, "b 2\nc\nc\nc\nc\n");
+        assert_eq!(stops(&output), vec!["1", "2", "2", "2"]);
+    }
+
+    #[test]
+    fn test_quit() {
+        let (output, result) = run(SOURCE, "b 6\nc\nq\n");
+        assert_eq!(stops(&output), vec!["1", "6"]);
+        assert!(matches!(result, Err(RuntimeError::Exit(0, _))));
+    }
+
+    #[test]
+    fn test_read_line_shares_input() {
+        // the program reads the line after the command that resumes it
+        let source = "var a = read_line();\nprint a;";
+        let output = debug(source, "b 2\nc\nhello\np a\nc\n");
+        assert_eq!(stops(&output), vec!["1", "2"]);
+        assert!(output.contains("a = hello\n"));
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt::Display;
+
+use crate::{
+    interpreter::runtime_error::RuntimeError, lexer::lex_error::LexError,
+    parser::parse_error::ParseError, resolver::resolve_error::ResolveError, token::span::Span,
+};
+
+// Every error rust-lox reports is a LoxError. Each one has a stable code that
+// `rust-lox explain <code>` describes, and the phase it happened in decides
+// the process exit code, following sysexits.h:
+//
+//   64  usage error, a bad command line or a file that can't be read
+//   65  the source has lexical, syntax or resolution errors
+//   70  a runtime error while the program was running
+//
+// The code is part of every reported message, like "Error[E0001]".
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LoxError {
+    #[error("Error[{}]: {0}", usage_code(.0))]
+    Usage(#[from] UsageError),
+
+    #[error("[line {}] Error[{}]: {0}", .0.line(), lex_code(.0))]
+    Lexical(#[from] LexError),
+
+    #[error("[line {}] Error[{}]{}: {0}", .0.token().line, parse_code(.0), .0.location())]
+    Syntax(#[from] ParseError),
+
+    #[error("[line {}] Error[{}] at '{}': {0}", .0.token().line, resolve_code(.0), .0.token().value)]
+    Resolution(#[from] ResolveError),
+
+    #[error("Error[{}]: {0}\n[line {}]", runtime_code(.0), .0.span().line)]
+    Runtime(#[from] RuntimeError),
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum UsageError {
+    #[error("Invalid command. Usage: {0} <{1}> <filename>")]
+    InvalidCommand(String, String),
+
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+
+    #[error("Unknown option: {0}")]
+    UnknownOption(String),
+
+    #[error("Unknown output format: {0}")]
+    UnknownFormat(String),
+
+    #[error("Failed to read file {0}")]
+    InvalidFile(String),
+
+    #[error("Unknown error code: {0}")]
+    UnknownErrorCode(String),
+
+    #[error("Invalid limit: {0}")]
+    InvalidLimit(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Usage,
+    Lexical,
+    Syntax,
+    Resolution,
+    Runtime,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Usage => write!(f, "usage"),
+            Phase::Lexical => write!(f, "lexical"),
+            Phase::Syntax => write!(f, "syntax"),
+            Phase::Resolution => write!(f, "resolution"),
+            Phase::Runtime => write!(f, "runtime"),
+        }
+    }
+}
+
+// codes are never reused, new errors get the next free code in their phase
+const ERROR_CODES: &[(&str, Phase, &str)] = &[
+    ("E0001", Phase::Lexical, "A character that does not start any token, like '@'."),
+    ("E0002", Phase::Lexical, "A string is missing its closing '\"'."),
+    ("E0003", Phase::Lexical, "A '${' in a string is missing its closing '}'."),
+    ("E0004", Phase::Lexical, "A '/*' comment is missing its closing '*/'. Block comments nest, so every '/*' needs its own '*/'."),
+    ("E0005", Phase::Lexical, "An unknown escape sequence in a string. Use \\n, \\t, \\r, \\0, \\\\, \\\", \\$ or \\u{...}."),
+    ("E0006", Phase::Lexical, "A \\u escape must be 1 to 6 hex digits in braces, naming a valid character, like \\u{1F600}."),
+    ("E0007", Phase::Lexical, "A malformed number, like 0b102, 0x or 1__0. '_' may only separate two digits."),
+    ("E0101", Phase::Syntax, "The parser expected a particular token, like a ';' after a statement."),
+    ("E0102", Phase::Syntax, "An expression was expected, like after an operator or '('."),
+    ("E0103", Phase::Syntax, "Only variables can be assigned to, so 'a + b = c' is an error."),
+    ("E0104", Phase::Syntax, "Functions can have at most 255 parameters and calls at most 255 arguments."),
+    ("E0201", Phase::Runtime, "A unary '-' was applied to a value that is not a number."),
+    ("E0202", Phase::Runtime, "An arithmetic or comparison operator was given a value that is not a number."),
+    ("E0203", Phase::Runtime, "'+' adds two numbers or concatenates two strings, it can't mix them."),
+    ("E0204", Phase::Runtime, "A variable was used or assigned before it was declared."),
+    ("E0205", Phase::Runtime, "Only functions can be called."),
+    ("E0206", Phase::Runtime, "A function was called with the wrong number of arguments."),
+    ("E0207", Phase::Runtime, "Integer arithmetic went outside the 64-bit range. Use a float, like 1.0, where exactness is not needed."),
+    ("E0208", Phase::Runtime, "An integer was divided by zero with '~/' or '%'."),
+    ("E0209", Phase::Runtime, "'~' was applied to a value that is not an integer."),
+    ("E0210", Phase::Runtime, "'&', '|', '^', '<<' and '>>' only work on integers."),
+    ("E0211", Phase::Runtime, "'<<' and '>>' shift by 0 to 63 bits."),
+    ("E0212", Phase::Runtime, "A constant was assigned to. Declare it with var if it needs to change."),
+    ("E0213", Phase::Runtime, "In strict mode, a global was defined again while the program was running."),
+    ("E0214", Phase::Runtime, "An imported file does not exist. Module paths are relative to the file that imports them."),
+    ("E0215", Phase::Runtime, "Modules imported each other, so neither can finish loading before the other."),
+    ("E0216", Phase::Runtime, "An imported file has lexical, syntax or resolution errors, which are reported above this one."),
+    ("E0217", Phase::Runtime, "'.' was used on a value that is not a module."),
+    ("E0218", Phase::Runtime, "A module does not declare the name that was read from or imported from it."),
+    ("E0219", Phase::Runtime, "Calls were nested deeper than the call depth limit, usually by recursion that never stops, or calls with blocks and expressions nested in them went deeper than the stack allows. Raise the call depth with --max-depth."),
+    ("E0220", Phase::Runtime, "The program ran more statements than --max-instructions allows."),
+    ("E0221", Phase::Runtime, "The program ran for longer than --timeout allows."),
+    ("E0222", Phase::Runtime, "The variables of the program held more memory than --max-heap allows."),
+    ("E0223", Phase::Runtime, "A native function needs a capability the program wasn't given. Allow it with --allow-fs-read, --allow-fs-write, --allow-fs, --allow-process or --allow-all. Time is allowed unless --deny-time is given."),
+    ("E0224", Phase::Runtime, "A native function was given the wrong kind of value, like a number where it reads a path."),
+    ("E0225", Phase::Runtime, "Reading or writing a file or directory failed. The message from the operating system says why."),
+    ("E0226", Phase::Runtime, "at() was given an index before the start or past the end of a list."),
+    ("E0227", Phase::Runtime, "exit() was called. The program stops with the code it was given, and nothing is reported."),
+    ("E0228", Phase::Runtime, "A named argument doesn't match any parameter of the function. A rest parameter can't be named."),
+    ("E0229", Phase::Runtime, "A parameter was given an argument twice, by name twice or by position and by name."),
+    ("E0230", Phase::Runtime, "A parameter without a default wasn't given an argument, by position or by name."),
+    ("E0231", Phase::Runtime, "Native functions only take positional arguments."),
+    ("E0301", Phase::Usage, "The command line is missing a command or a filename."),
+    ("E0302", Phase::Usage, "The command is not one of tokenize, parse, interpret, debug, explain or lsp."),
+    ("E0303", Phase::Usage, "An option that the command does not understand."),
+    ("E0304", Phase::Usage, "--format must be text or json."),
+    ("E0305", Phase::Usage, "The source file does not exist or can't be read."),
+    ("E0306", Phase::Usage, "explain was given a code that is not listed."),
+    ("E0307", Phase::Usage, "--max-depth, --max-instructions, --timeout and --max-heap take a whole number."),
+    ("E0401", Phase::Resolution, "A constant was assigned to. Declare it with var if it needs to change."),
+    ("E0402", Phase::Resolution, "A constant was declared again in the same scope."),
+    ("E0403", Phase::Resolution, "In strict mode, a global can only be declared once."),
+    ("E0404", Phase::Resolution, "In strict mode, nothing can be declared with the name of a native function like clock."),
+    ("E0405", Phase::Resolution, "In strict mode, every variable must be declared, either in an enclosing scope or as a global somewhere in the program."),
+];
+
+impl LoxError {
+    pub fn phase(&self) -> Phase {
+        match self {
+            LoxError::Usage(_) => Phase::Usage,
+            LoxError::Lexical(_) => Phase::Lexical,
+            LoxError::Syntax(_) => Phase::Syntax,
+            LoxError::Resolution(_) => Phase::Resolution,
+            LoxError::Runtime(_) => Phase::Runtime,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            LoxError::Usage(error) => usage_code(error),
+            LoxError::Lexical(error) => lex_code(error),
+            LoxError::Syntax(error) => parse_code(error),
+            LoxError::Resolution(error) => resolve_code(error),
+            LoxError::Runtime(error) => runtime_code(error),
+        }
+    }
+
+    // usage errors happen before there is any source to point at
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LoxError::Usage(_) => None,
+            LoxError::Lexical(error) => Some(error.span()),
+            LoxError::Syntax(error) => Some(error.token().span),
+            LoxError::Resolution(error) => Some(error.token().span),
+            LoxError::Runtime(error) => Some(error.span()),
+        }
+    }
+
+    // the message without the line it was found on, for tools that show
+    // where the error is themselves
+    pub fn message(&self) -> String {
+        match self {
+            LoxError::Usage(error) => error.to_string(),
+            LoxError::Lexical(error) => error.to_string(),
+            LoxError::Syntax(error) => error.to_string(),
+            LoxError::Resolution(error) => error.to_string(),
+            LoxError::Runtime(error) => error.to_string(),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        if let LoxError::Runtime(RuntimeError::Exit(code, _)) = self {
+            return *code;
+        }
+
+        match self.phase() {
+            Phase::Usage => 64,
+            Phase::Lexical | Phase::Syntax | Phase::Resolution => 65,
+            Phase::Runtime => 70,
+        }
+    }
+}
+
+fn usage_code(error: &UsageError) -> &'static str {
+    match error {
+        UsageError::InvalidCommand(_, _) => "E0301",
+        UsageError::UnknownCommand(_) => "E0302",
+        UsageError::UnknownOption(_) => "E0303",
+        UsageError::UnknownFormat(_) => "E0304",
+        UsageError::InvalidFile(_) => "E0305",
+        UsageError::UnknownErrorCode(_) => "E0306",
+        UsageError::InvalidLimit(_) => "E0307",
+    }
+}
+
+fn lex_code(error: &LexError) -> &'static str {
+    match error {
+        LexError::UnexpectedCharacter(_, _) => "E0001",
+        LexError::UnterminatedString(_) => "E0002",
+        LexError::UnterminatedInterpolation(_) => "E0003",
+        LexError::UnterminatedBlockComment(_) => "E0004",
+        LexError::InvalidEscape(_, _) => "E0005",
+        LexError::InvalidUnicodeEscape(_, _) => "E0006",
+        LexError::InvalidNumber(_, _, _) => "E0007",
+    }
+}
+
+fn parse_code(error: &ParseError) -> &'static str {
+    match error {
+        ParseError::UnexpectedToken(_, _) => "E0101",
+        ParseError::ExpectedExpression(_) => "E0102",
+        ParseError::InvalidAssignmentTarget(_) => "E0103",
+        ParseError::TooManyItems(_, _) => "E0104",
+    }
+}
+
+fn resolve_code(error: &ResolveError) -> &'static str {
+    match error {
+        ResolveError::ConstantAssignment(_) => "E0401",
+        ResolveError::ConstantRedeclaration(_) => "E0402",
+        ResolveError::GlobalRedeclaration(_) => "E0403",
+        ResolveError::NativeShadowing(_) => "E0404",
+        ResolveError::UndefinedVariable(_) => "E0405",
+    }
+}
+
+fn runtime_code(error: &RuntimeError) -> &'static str {
+    match error {
+        RuntimeError::NumberOperand(_) => "E0201",
+        RuntimeError::NumberOperands(_) => "E0202",
+        RuntimeError::AddOperands(_) => "E0203",
+        RuntimeError::UndefinedVariable(_, _) => "E0204",
+        RuntimeError::NotCallable(_) => "E0205",
+        RuntimeError::ArityMismatch(_, _, _) => "E0206",
+        RuntimeError::IntegerOverflow(_) => "E0207",
+        RuntimeError::DivisionByZero(_) => "E0208",
+        RuntimeError::IntegerOperand(_) => "E0209",
+        RuntimeError::IntegerOperands(_) => "E0210",
+        RuntimeError::InvalidShift(_, _) => "E0211",
+        RuntimeError::ConstantAssignment(_, _) => "E0212",
+        RuntimeError::GlobalRedefinition(_, _) => "E0213",
+        RuntimeError::ModuleNotFound(_, _) => "E0214",
+        RuntimeError::ImportCycle(_, _) => "E0215",
+        RuntimeError::InvalidModule(_, _) => "E0216",
+        RuntimeError::NoProperties(_) => "E0217",
+        RuntimeError::UndefinedProperty(_, _) => "E0218",
+        RuntimeError::StackOverflow(_) => "E0219",
+        RuntimeError::InstructionLimit(_, _) => "E0220",
+        RuntimeError::TimeLimit(_, _) => "E0221",
+        RuntimeError::HeapLimit(_, _) => "E0222",
+        RuntimeError::MissingCapability(_, _, _) => "E0223",
+        RuntimeError::ArgumentType(_, _, _, _) => "E0224",
+        RuntimeError::Io(_, _, _, _) => "E0225",
+        RuntimeError::IndexOutOfRange(_, _) => "E0226",
+        RuntimeError::Exit(_, _) => "E0227",
+        RuntimeError::UnknownArgument(_, _) => "E0228",
+        RuntimeError::DuplicateArgument(_, _) => "E0229",
+        RuntimeError::MissingArgument(_, _) => "E0230",
+        RuntimeError::NamedArguments(_, _) => "E0231",
+    }
+}
+
+// the long form description of an error code, for `rust-lox explain`
+pub fn explain(code: &str) -> Option<String> {
+    ERROR_CODES
+        .iter()
+        .find(|(known, _, _)| known.eq_ignore_ascii_case(code))
+        .map(|(code, phase, description)| format!("{} ({} error)\n{}", code, phase, description))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::Token;
+    use rstest::*;
+
+    #[rstest]
+    #[case(LexError::UnterminatedString(Span::default()).into(), "E0002", Phase::Lexical, 65)]
+    #[case(ParseError::ExpectedExpression(Box::new(Token::new_eof(1))).into(), "E0102", Phase::Syntax, 65)]
+    #[case(RuntimeError::NotCallable(Span::default()).into(), "E0205", Phase::Runtime, 70)]
+    #[case(UsageError::UnknownCommand("x".to_string()).into(), "E0302", Phase::Usage, 64)]
+    fn test_error_codes(
+        #[case] error: LoxError,
+        #[case] code: &str,
+        #[case] phase: Phase,
+        #[case] exit_code: i32,
+    ) {
+        assert_eq!(error.code(), code);
+        assert_eq!(error.phase(), phase);
+        assert_eq!(error.exit_code(), exit_code);
+        assert!(explain(code).is_some());
+    }
+
+    #[test]
+    fn test_error_codes_are_unique() {
+        let codes: Vec<_> = ERROR_CODES.iter().map(|(code, _, _)| code).collect();
+        assert!(codes.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_reports() {
+        let token =
+            Token::new_identifier(3, "a".to_string()).with_span(Span::new(0, 1, (3, 1), (3, 2)));
+        let mut named = token.clone();
+        named.lexeme = Some("a".to_string());
+
+        let error: LoxError =
+            ParseError::UnexpectedToken(Box::new(named), "Expect ';'.".to_string()).into();
+        assert_eq!(
+            error.to_string(),
+            "[line 3] Error[E0101] at 'a': Expect ';'."
+        );
+
+        let error: LoxError = RuntimeError::UndefinedVariable("a".to_string(), token.span).into();
+        assert_eq!(
+            error.to_string(),
+            "Error[E0204]: Undefined variable 'a'.\n[line 3]"
+        );
+        assert_eq!(error.span(), Some(token.span));
+
+        let error: LoxError = LexError::UnexpectedCharacter('@', token.span).into();
+        assert_eq!(
+            error.to_string(),
+            "[line 3] Error[E0001]: Unexpected character: @"
+        );
+
+        let error: LoxError = ResolveError::UndefinedVariable(Box::new(token)).into();
+        assert_eq!(
+            error.to_string(),
+            "[line 3] Error[E0405] at 'a': Undefined variable 'a'."
+        );
+
+        let error: LoxError = UsageError::UnknownCommand("run".to_string()).into();
+        assert_eq!(error.to_string(), "Error[E0302]: Unknown command: run");
+    }
+}
@@ -1,17 +1,19 @@
 use crate::{
+    error::{LoxError, Phase},
+    lexer::lex_error::LexError,
     parser::parse_error::ParseError,
-    token::{token_type::TokenType, token_value::TokenValue, Token},
 };
 
+// Collects the errors found in the source, writing each one as it is reported
 pub struct ErrorBag {
-    failed: bool,
+    errors: Vec<LoxError>,
     writer: Box<dyn std::io::Write>,
 }
 
 impl Default for ErrorBag {
     fn default() -> Self {
         Self {
-            failed: false,
+            errors: Vec::new(),
             writer: Box::new(std::io::stderr()),
         }
     }
@@ -20,52 +22,84 @@ impl Default for ErrorBag {
 impl ErrorBag {
     pub fn new(writer: Box<dyn std::io::Write>) -> Self {
         Self {
-            failed: false,
+            errors: Vec::new(),
             writer,
         }
     }
 
     pub fn has_error(&self) -> bool {
-        self.failed
+        !self.errors.is_empty()
     }
 
-    pub fn parse_error(&mut self, token: &Token, message: &str) -> ParseError {
-        let report_result = match (&token.token_type, &token.value) {
-            (TokenType::Eof, _) => self.report(token.line, " at end", message),
-            (_, TokenValue::None) => self.report(
-                token.line,
-                format!(" at '{}'", token.token_type).as_str(),
-                message,
-            ),
-            _ => self.report(
-                token.line,
-                format!(" at '{}'", token.value).as_str(),
-                message,
-            ),
-        };
+    pub fn has_lex_error(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|error| error.phase() == Phase::Lexical)
+    }
+
+    pub fn errors(&self) -> &[LoxError] {
+        &self.errors
+    }
+
+    // once the lexer has failed, syntax errors are usually caused by the bad
+    // token, so they aren't reported
+    pub fn parse_error(&mut self, error: ParseError) -> ParseError {
+        if !self.has_lex_error() {
+            self.report(error.clone().into());
+        }
+        error
+    }
 
-        report_result.expect("Failed to write to writer");
+    pub fn report_lex_error(&mut self, error: LexError) {
+        self.report(error.into());
+    }
 
-        self.failed = true;
-        ParseError::Error(token.token_type, message.to_string())
+    pub fn report(&mut self, error: LoxError) {
+        writeln!(self.writer.as_mut(), "{}", error).expect("Failed to write to writer");
+        self.errors.push(error);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser, test_output::Output, token::span::Span};
+
+    #[test]
+    fn test_lex_errors() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let source = "var a = 1 @;\nprint \"\\q\";\n  $";
+        let _ = Parser::new(&mut errors, Lexer::new(source)).parse();
+        assert!(errors.has_lex_error());
 
-    pub fn report(
-        &mut self,
-        line: usize,
-        location: &str,
-        message: &str,
-    ) -> Result<(), std::io::Error> {
-        writeln!(
-            self.writer.as_mut(),
-            "[line {line}] Error{location}: {message}",
-            line = line,
-            location = location,
-            message = message
-        )
+        let lex_errors: Vec<&LexError> = errors
+            .errors()
+            .iter()
+            .filter_map(|error| match error {
+                LoxError::Lexical(error) => Some(error),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            lex_errors,
+            [
+                &LexError::UnexpectedCharacter('@', Span::new(10, 11, (1, 11), (1, 12))),
+                &LexError::InvalidEscape('q', Span::new(20, 22, (2, 8), (2, 10))),
+                &LexError::UnexpectedCharacter('$', Span::new(27, 28, (3, 3), (3, 4))),
+            ]
+        );
     }
 
-    pub fn report_lex_error(&self, line: usize, message: &str) {
-        eprintln!("[line {}] Error: {}", line, message);
+    #[test]
+    fn test_reports_carry_codes() {
+        let output = Output::default();
+        let mut errors = ErrorBag::new(Box::new(output.clone()));
+        let _ = Parser::new(&mut errors, Lexer::new("print 1 +;")).parse();
+        let _ = Parser::new(&mut errors, Lexer::new("\"a")).parse();
+        assert_eq!(
+            output.contents(),
+            "[line 1] Error[E0102] at ';': Expect expression.\n\
+             [line 1] Error[E0002]: Unterminated string.\n"
+        );
     }
 }
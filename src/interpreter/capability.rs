@@ -0,0 +1,42 @@
+use std::fmt::Display;
+
+// What a native function can reach outside the interpreter. A call to a
+// native that needs a capability the interpreter wasn't given is a runtime
+// error, so untrusted scripts can only compute and print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Time,
+    FsRead,
+    FsWrite,
+    Process,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [
+        Capability::Time,
+        Capability::FsRead,
+        Capability::FsWrite,
+        Capability::Process,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Time => "time",
+            Capability::FsRead => "fs-read",
+            Capability::FsWrite => "fs-write",
+            Capability::Process => "process",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.name() == name)
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
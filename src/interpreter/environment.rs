@@ -1,74 +1,150 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    rc::Rc,
+};
 
-use crate::parser::object::Object;
+use crate::{
+    parser::object::Object,
+    token::{token_value::TokenValue, Token},
+};
 
-use super::interpreter_error::InterpreterError;
+use super::runtime_error::RuntimeError;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Environment {
     parent: Option<Rc<RefCell<Environment>>>,
     values: HashMap<String, Object>,
+    // the names in `values` that were declared with const
+    constants: HashSet<String>,
+    // the bytes held by every environment under the same globals, shared
+    // with the parent
+    heap: Rc<Cell<usize>>,
+    // this environment's part of `heap`
+    size: usize,
 }
 
 impl Environment {
     pub fn new(parent: Option<Rc<RefCell<Environment>>>) -> Self {
+        let heap = match &parent {
+            Some(parent) => Rc::clone(&parent.borrow().heap),
+            None => Rc::new(Cell::new(0)),
+        };
         Environment {
             parent,
             values: HashMap::new(),
+            constants: HashSet::new(),
+            heap,
+            size: 0,
         }
     }
 
     pub fn new_with_parent(parent: Rc<RefCell<Environment>>) -> Self {
-        Environment {
-            parent: Some(parent),
-            values: HashMap::new(),
-        }
+        Self::new(Some(parent))
     }
 
-    pub fn assign(&mut self, name: &str, value: Object) -> Result<(), InterpreterError> {
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_owned(), value);
+    pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), RuntimeError> {
+        let key = identifier(name);
+        if self.constants.contains(key) {
+            return Err(RuntimeError::ConstantAssignment(key.to_owned(), name.span));
+        }
+
+        if self.values.contains_key(key) {
+            self.insert(key, value);
             return Ok(());
         }
 
         if let Some(parent) = &self.parent {
             parent.borrow_mut().assign(name, value)
         } else {
-            Err(InterpreterError::RuntimeError(format!(
-                "Undefined variable '{}'.",
-                name
-            )))
+            Err(RuntimeError::UndefinedVariable(key.to_owned(), name.span))
         }
     }
 
+    // whether the name is defined in this environment, ignoring its parents
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
     pub fn define(&mut self, name: &str, value: Object) {
-        self.values.insert(name.to_owned(), value);
+        self.constants.remove(name);
+        self.insert(name, value);
+    }
+
+    pub fn define_constant(&mut self, name: &str, value: Object) {
+        self.insert(name, value);
+        self.constants.insert(name.to_owned());
     }
 
-    pub fn get(&self, name: &str) -> Result<Object, InterpreterError> {
-        if let Some(value) = self.values.get(name) {
+    // the approximate bytes held by this environment, its parents and every
+    // other environment that shares its globals
+    pub fn heap(&self) -> usize {
+        self.heap.get()
+    }
+
+    fn insert(&mut self, name: &str, value: Object) {
+        let size = name.len() + value.size();
+        let previous = self.values.insert(name.to_owned(), value);
+        let freed = previous.map_or(0, |previous| name.len() + previous.size());
+        self.size = self.size + size - freed;
+        self.heap.set(self.heap.get() + size - freed);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Object, RuntimeError> {
+        let key = identifier(name);
+        if let Some(value) = self.values.get(key) {
             return Ok(value.clone());
         }
 
         if let Some(parent) = &self.parent {
             parent.borrow().get(name)
         } else {
-            Err(InterpreterError::UndefinedVariable(name.to_owned()))
+            Err(RuntimeError::UndefinedVariable(key.to_owned(), name.span))
         }
     }
 
-    pub fn print(&self) {
-        self.print_internal(0);
+    pub fn names(&self) -> Vec<String> {
+        self.values.keys().cloned().collect()
     }
 
-    fn print_internal(&self, depth: usize) {
-        if let Some(parent) = &self.parent {
-            parent.borrow().print_internal(depth + 1);
+    // writes the names in this scope and then each enclosing scope, sorted
+    // and indented one step further for every scope out
+    pub fn print(&self, output: &mut dyn Write) -> io::Result<()> {
+        self.print_internal(output, 0)
+    }
+
+    fn print_internal(&self, output: &mut dyn Write, depth: usize) -> io::Result<()> {
+        let mut values: Vec<_> = self.values.iter().collect();
+        values.sort_by_key(|(key, _)| *key);
+        for (key, value) in values {
+            let constant = if self.constants.contains(key) {
+                " (const)"
+            } else {
+                ""
+            };
+            writeln!(
+                output,
+                "{:indent$}{}: {}{}",
+                "",
+                key,
+                value,
+                constant,
+                indent = depth * 2
+            )?;
         }
 
-        for (key, value) in &self.values {
-            println!("{:indent$}{}: {}", "", key, value, indent = depth * 2);
+        if let Some(parent) = &self.parent {
+            parent.borrow().print_internal(output, depth + 1)?;
         }
+        Ok(())
+    }
+}
+
+fn identifier(name: &Token) -> &str {
+    match &name.value {
+        TokenValue::Identifier(name) => name,
+        _ => unreachable!("Variable name must be an identifier"),
     }
 }
 
@@ -77,3 +153,9 @@ impl Default for Environment {
         Self::new(None)
     }
 }
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        self.heap.set(self.heap.get() - self.size);
+    }
+}
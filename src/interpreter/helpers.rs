@@ -1,32 +1,307 @@
-use crate::{parser::object::Object, token::token_type::TokenType};
+use std::cmp::Ordering;
 
-use super::interpreter_error::InterpreterError;
+use crate::{
+    parser::object::Object,
+    token::{token_type::TokenType, Token},
+};
+
+use super::runtime_error::RuntimeError;
 
 pub(crate) fn check_number_operand(
-    operator: &TokenType,
+    operator: &Token,
     operand: &Object,
-) -> Result<(), InterpreterError> {
-    if let Object::Number(_) = operand {
-        Ok(())
-    } else {
-        Err(InterpreterError::RuntimeError(format!(
-            "Operand must be a number for operator ({} {})",
-            operator, operand
-        )))
-    }
-}
-
-pub(crate) fn check_number_operands<'a>(
-    left: &'a Object,
-    operator: &TokenType,
-    right: &'a Object,
-) -> Result<(f64, f64), InterpreterError> {
-    if let (Object::Number(left), Object::Number(right)) = (left, right) {
-        Ok((*left, *right))
-    } else {
-        Err(InterpreterError::RuntimeError(format!(
-            "Operands must be numbers for operator ({} {} {})",
-            left, operator, right
-        )))
+) -> Result<f64, RuntimeError> {
+    operand
+        .as_number()
+        .ok_or(RuntimeError::NumberOperand(operator.span))
+}
+
+pub(crate) fn check_number_operands(
+    left: &Object,
+    operator: &Token,
+    right: &Object,
+) -> Result<(f64, f64), RuntimeError> {
+    match (left.as_number(), right.as_number()) {
+        (Some(left), Some(right)) => Ok((left, right)),
+        _ => Err(RuntimeError::NumberOperands(operator.span)),
+    }
+}
+
+// integers stay exact while both operands are integers, mixing in a float
+// promotes the whole operation to floating point
+pub(crate) fn arithmetic(
+    left: &Object,
+    operator: &Token,
+    right: &Object,
+) -> Result<Object, RuntimeError> {
+    if let (Object::Integer(left), Object::Integer(right)) = (left, right) {
+        return integer_arithmetic(*left, operator, *right);
+    }
+
+    let (left, right) = check_number_operands(left, operator, right)?;
+    let value = match operator.token_type {
+        TokenType::Plus => left + right,
+        TokenType::Minus => left - right,
+        TokenType::Star => left * right,
+        TokenType::Slash => left / right,
+        TokenType::Percent => left % right,
+        TokenType::TildeSlash => (left / right).trunc(),
+        TokenType::StarStar => left.powf(right),
+        _ => unreachable!("Invalid arithmetic operator {}", operator.token_type),
+    };
+
+    Ok(Object::Number(value))
+}
+
+fn integer_arithmetic(left: i64, operator: &Token, right: i64) -> Result<Object, RuntimeError> {
+    let value = match operator.token_type {
+        TokenType::Plus => left.checked_add(right),
+        TokenType::Minus => left.checked_sub(right),
+        TokenType::Star => left.checked_mul(right),
+        // "/" only stays an integer when the division is exact, 7 / 2 is 3.5
+        TokenType::Slash => match left.checked_rem(right) {
+            Some(0) => left.checked_div(right),
+            _ => return Ok(Object::Number(left as f64 / right as f64)),
+        },
+        // "~/" truncates towards zero and "%" takes the sign of the left operand
+        TokenType::TildeSlash | TokenType::Percent if right == 0 => {
+            return Err(RuntimeError::DivisionByZero(operator.span))
+        }
+        TokenType::TildeSlash => left.checked_div(right),
+        TokenType::Percent => left.checked_rem(right),
+        // a negative power is a fraction, so it can't stay an integer
+        TokenType::StarStar if right < 0 => {
+            return Ok(Object::Number((left as f64).powf(right as f64)))
+        }
+        TokenType::StarStar => u32::try_from(right)
+            .ok()
+            .and_then(|right| left.checked_pow(right)),
+        _ => unreachable!("Invalid arithmetic operator {}", operator.token_type),
+    };
+
+    value
+        .map(Object::Integer)
+        .ok_or(RuntimeError::IntegerOverflow(operator.span))
+}
+
+// the bitwise operators only work on integers
+pub(crate) fn bitwise(
+    left: &Object,
+    operator: &Token,
+    right: &Object,
+) -> Result<Object, RuntimeError> {
+    let (Object::Integer(left), Object::Integer(right)) = (left, right) else {
+        return Err(RuntimeError::IntegerOperands(operator.span));
+    };
+
+    let value = match operator.token_type {
+        TokenType::Ampersand => left & right,
+        TokenType::Pipe => left | right,
+        TokenType::Caret => left ^ right,
+        TokenType::LessLess | TokenType::GreaterGreater if !(0..64).contains(right) => {
+            return Err(RuntimeError::InvalidShift(*right, operator.span))
+        }
+        TokenType::LessLess => {
+            // shifting bits out of the top is an overflow like any other
+            let value = left << right;
+            if value >> right != *left {
+                return Err(RuntimeError::IntegerOverflow(operator.span));
+            }
+            value
+        }
+        TokenType::GreaterGreater => left >> right,
+        _ => unreachable!("Invalid bitwise operator {}", operator.token_type),
+    };
+
+    Ok(Object::Integer(value))
+}
+
+pub(crate) fn complement(operator: &Token, operand: &Object) -> Result<Object, RuntimeError> {
+    match operand {
+        Object::Integer(num) => Ok(Object::Integer(!num)),
+        _ => Err(RuntimeError::IntegerOperand(operator.span)),
+    }
+}
+
+pub(crate) fn compare(
+    left: &Object,
+    operator: &Token,
+    right: &Object,
+) -> Result<Option<Ordering>, RuntimeError> {
+    check_number_operands(left, operator, right)?;
+    Ok(left.partial_cmp(right))
+}
+
+pub(crate) fn negate(operator: &Token, operand: &Object) -> Result<Object, RuntimeError> {
+    match operand {
+        Object::Integer(num) => num
+            .checked_neg()
+            .map(Object::Integer)
+            .ok_or(RuntimeError::IntegerOverflow(operator.span)),
+        _ => Ok(Object::Number(-check_number_operand(operator, operand)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::span::Span;
+    use rstest::*;
+
+    fn operator(token_type: TokenType) -> Token {
+        Token::from_token_type(1, token_type)
+    }
+
+    #[rstest]
+    #[case(
+        Object::Integer(2),
+        TokenType::Plus,
+        Object::Integer(3),
+        Object::Integer(5)
+    )]
+    #[case(
+        Object::Integer(2),
+        TokenType::Plus,
+        Object::Number(0.5),
+        Object::Number(2.5)
+    )]
+    #[case(
+        Object::Integer(6),
+        TokenType::Slash,
+        Object::Integer(3),
+        Object::Integer(2)
+    )]
+    #[case(
+        Object::Integer(7),
+        TokenType::Slash,
+        Object::Integer(2),
+        Object::Number(3.5)
+    )]
+    #[case(Object::Integer(-7), TokenType::TildeSlash, Object::Integer(2), Object::Integer(-3))]
+    #[case(Object::Integer(-7), TokenType::Percent, Object::Integer(2), Object::Integer(-1))]
+    #[case(
+        Object::Number(7.5),
+        TokenType::Percent,
+        Object::Integer(2),
+        Object::Number(1.5)
+    )]
+    #[case(
+        Object::Number(7.5),
+        TokenType::TildeSlash,
+        Object::Integer(2),
+        Object::Number(3.0)
+    )]
+    #[case(Object::Integer(2), TokenType::StarStar, Object::Integer(62), Object::Integer(1 << 62))]
+    #[case(Object::Integer(2), TokenType::StarStar, Object::Integer(-1), Object::Number(0.5))]
+    #[case(
+        Object::Number(4.0),
+        TokenType::StarStar,
+        Object::Number(0.5),
+        Object::Number(2.0)
+    )]
+    #[case(
+        Object::Integer(9_007_199_254_740_993),
+        TokenType::Star,
+        Object::Integer(1),
+        Object::Integer(9_007_199_254_740_993)
+    )]
+    fn test_arithmetic(
+        #[case] left: Object,
+        #[case] token_type: TokenType,
+        #[case] right: Object,
+        #[case] expected: Object,
+    ) {
+        let result = arithmetic(&left, &operator(token_type), &right).unwrap();
+        assert_eq!(result.to_string(), expected.to_string());
+        assert_eq!(
+            std::mem::discriminant(&result),
+            std::mem::discriminant(&expected)
+        );
+    }
+
+    #[rstest]
+    #[case(Object::Integer(i64::MAX), TokenType::Plus, Object::Integer(1))]
+    #[case(Object::Integer(i64::MIN), TokenType::Minus, Object::Integer(1))]
+    #[case(Object::Integer(i64::MAX), TokenType::Star, Object::Integer(2))]
+    #[case(Object::Integer(i64::MIN), TokenType::TildeSlash, Object::Integer(-1))]
+    #[case(Object::Integer(2), TokenType::StarStar, Object::Integer(63))]
+    fn test_integer_overflow(
+        #[case] left: Object,
+        #[case] token_type: TokenType,
+        #[case] right: Object,
+    ) {
+        let result = arithmetic(&left, &operator(token_type), &right);
+        assert!(matches!(result, Err(RuntimeError::IntegerOverflow(_))));
+    }
+
+    #[rstest]
+    #[case(TokenType::TildeSlash)]
+    #[case(TokenType::Percent)]
+    fn test_division_by_zero(#[case] token_type: TokenType) {
+        let result = arithmetic(
+            &Object::Integer(1),
+            &operator(token_type),
+            &Object::Integer(0),
+        );
+        assert!(matches!(result, Err(RuntimeError::DivisionByZero(_))));
+    }
+
+    #[rstest]
+    #[case(12, TokenType::Ampersand, 10, Ok(8))]
+    #[case(12, TokenType::Pipe, 10, Ok(14))]
+    #[case(12, TokenType::Caret, 10, Ok(6))]
+    #[case(1, TokenType::LessLess, 62, Ok(1 << 62))]
+    #[case(-16, TokenType::GreaterGreater, 2, Ok(-4))]
+    #[case(
+        1,
+        TokenType::LessLess,
+        63,
+        Err(RuntimeError::IntegerOverflow(Span::default()))
+    )]
+    #[case(
+        1,
+        TokenType::LessLess,
+        64,
+        Err(RuntimeError::InvalidShift(64, Span::default()))
+    )]
+    #[case(1, TokenType::GreaterGreater, -1, Err(RuntimeError::InvalidShift(-1, Span::default())))]
+    fn test_bitwise(
+        #[case] left: i64,
+        #[case] token_type: TokenType,
+        #[case] right: i64,
+        #[case] expected: Result<i64, RuntimeError>,
+    ) {
+        let result = bitwise(
+            &Object::Integer(left),
+            &operator(token_type),
+            &Object::Integer(right),
+        );
+        assert_eq!(result, expected.map(Object::Integer));
+    }
+
+    #[test]
+    fn test_bitwise_needs_integers() {
+        let result = bitwise(
+            &Object::Number(1.0),
+            &operator(TokenType::Pipe),
+            &Object::Integer(1),
+        );
+        assert!(matches!(result, Err(RuntimeError::IntegerOperands(_))));
+        assert_eq!(
+            complement(&operator(TokenType::Tilde), &Object::Integer(5)),
+            Ok(Object::Integer(-6))
+        );
+        assert!(complement(&operator(TokenType::Tilde), &Object::Number(5.0)).is_err());
+    }
+
+    #[test]
+    fn test_negate() {
+        let minus = operator(TokenType::Minus);
+        assert_eq!(negate(&minus, &Object::Integer(3)), Ok(Object::Integer(-3)));
+        assert!(negate(&minus, &Object::Integer(i64::MIN)).is_err());
+        assert_eq!(
+            negate(&minus, &Object::Number(1.5)),
+            Ok(Object::Number(-1.5))
+        );
     }
 }
@@ -0,0 +1,72 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    parser::{object::Object, statement::Statement},
+    token::Token,
+};
+
+use super::{runtime_error::RuntimeError, Interpreter};
+
+// Lets a tool like the debugger watch a program run. The interpreter hands
+// itself to the hook before every statement and around every call, so the
+// hook can look at the call stack and the environment the program is in.
+pub trait Hook {
+    // an error stops the program before the statement runs
+    fn before_statement(
+        &mut self,
+        _interpreter: &Interpreter,
+        _statement: &Statement,
+    ) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    // the new frame is the last of the interpreter's frames
+    fn on_call(&mut self, _interpreter: &Interpreter) {}
+
+    // the returning frame is still the last of the interpreter's frames,
+    // `value` is None when the call failed
+    fn on_return(&mut self, _interpreter: &Interpreter, _value: Option<&Object>) {}
+
+    // `at` is the keyword of an if or while, or the operator of an and, or
+    // or ??. Branch 0 is the then branch, entering the loop body or stopping
+    // at the left operand, and branch 1 is the else branch, leaving the loop
+    // or evaluating the right operand.
+    fn on_branch(&mut self, _interpreter: &Interpreter, _at: &Token, _branch: usize) {}
+}
+
+// a shared hook, so whoever installed it can read what it collected afterwards
+impl<T: Hook> Hook for Rc<RefCell<T>> {
+    fn before_statement(
+        &mut self,
+        interpreter: &Interpreter,
+        statement: &Statement,
+    ) -> Result<(), RuntimeError> {
+        self.borrow_mut().before_statement(interpreter, statement)
+    }
+
+    fn on_call(&mut self, interpreter: &Interpreter) {
+        self.borrow_mut().on_call(interpreter);
+    }
+
+    fn on_return(&mut self, interpreter: &Interpreter, value: Option<&Object>) {
+        self.borrow_mut().on_return(interpreter, value);
+    }
+
+    fn on_branch(&mut self, interpreter: &Interpreter, at: &Token, branch: usize) {
+        self.borrow_mut().on_branch(interpreter, at, branch);
+    }
+}
+
+// a function call that hasn't returned yet, or the script itself at the bottom
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub name: String,
+    // the line of the statement the frame is running
+    pub line: usize,
+}
+
+impl Frame {
+    pub fn new(name: String) -> Self {
+        Self { name, line: 0 }
+    }
+}
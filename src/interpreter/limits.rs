@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+// The interpreter recurses on the native stack, so main runs it on a thread
+// with this much stack rather than whatever the main thread was given.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+// A statement or expression nested in another takes up to about 8 KiB of
+// stack in a debug build, counting the calls between them, so the default
+// nesting uses at most half of the stack.
+const NESTING_COST: usize = 8 * 1024;
+
+// How far a program may go before it is stopped with a runtime error. Only
+// the call depth and nesting are limited by default, since running out of
+// the Rust stack aborts the whole process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    // nested calls, not counting the script itself
+    pub call_depth: usize,
+    // statements and expressions inside each other, across calls
+    pub nesting: usize,
+    // statements executed, including those in imported modules
+    pub instructions: Option<u64>,
+    pub time: Option<Duration>,
+    // approximate bytes held by variables, see `Object::size`
+    pub heap: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            call_depth: 256,
+            nesting: STACK_SIZE / 2 / NESTING_COST,
+            instructions: None,
+            time: None,
+            heap: None,
+        }
+    }
+}
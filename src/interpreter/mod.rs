@@ -1,68 +1,370 @@
+pub mod capability;
 pub mod environment;
-pub mod interpreter_error;
+pub mod hook;
+pub mod limits;
+pub mod runtime_error;
+pub mod script_statements;
 
 mod helpers;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
+};
 
+use capability::Capability;
 use environment::Environment;
-use helpers::{check_number_operand, check_number_operands};
-use interpreter_error::InterpreterError;
+use helpers::{arithmetic, bitwise, compare, complement, negate};
+use hook::{Frame, Hook};
+use limits::Limits;
+use runtime_error::RuntimeError;
 
 use crate::{
+    error_bag::ErrorBag,
+    lexer::Lexer,
     parser::{
-        callable::Clock, expression::Expression, function::Function, object::Object,
-        statement::Statement,
+        callable::{
+            Args, At, Callable, Clock, Exit, Extreme, Len, ListDir, ReadFile, ReadLine, WriteFile,
+        },
+        expression::Expression,
+        function::Function,
+        module::Module,
+        object::Object,
+        statement::{Parameter, Statement},
+        Parser,
     },
-    token::{token_type::TokenType, token_value::TokenValue, Token},
+    resolver::Resolver,
+    token::{span::Span, token_type::TokenType, token_value::TokenValue, Token},
     visitor::{expression_visitor::ExpressionVisitor, statement_visitor::StatementVisitor},
 };
 
+// where read_line reads from, shared so the debugger can read its commands
+// from the same stdin without either one buffering lines meant for the other
+pub type Input = Rc<RefCell<dyn BufRead>>;
+
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     pub environment: Rc<RefCell<environment::Environment>>,
+    // reject defining a global that already exists, natives included
+    strict: bool,
+    // imports are relative to the directory of the file being run
+    directory: PathBuf,
+    // every module by its canonical path, None while it is still running
+    modules: HashMap<PathBuf, Option<Object>>,
+    // the calls being run, innermost last
+    frames: Vec<Frame>,
+    hooks: Vec<Box<dyn Hook>>,
+    limits: Limits,
+    // what natives may do, nothing unless it is allowed
+    capabilities: HashSet<Capability>,
+    // statements executed so far, and when the program started
+    instructions: u64,
+    // statements and expressions being run inside each other
+    depth: usize,
+    started: Instant,
+    // where the innermost call was made, for natives to report errors at
+    call_site: Span,
+    // the arguments after the script name, and where read_line reads from
+    args: Vec<String>,
+    input: Input,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let globals = Rc::new(RefCell::new(Environment::default()));
-
-        // register native functions
-        globals
-            .borrow_mut()
-            .define("clock", Object::Callable(Box::new(Clock {})));
+        let globals = new_globals();
 
         Self {
             globals: Rc::clone(&globals),
             environment: Rc::clone(&globals),
+            strict: false,
+            directory: PathBuf::from("."),
+            modules: HashMap::new(),
+            frames: vec![Frame::new("script".to_string())],
+            hooks: Vec::new(),
+            limits: Limits::default(),
+            capabilities: HashSet::new(),
+            instructions: 0,
+            depth: 0,
+            started: Instant::now(),
+            call_site: Span::default(),
+            args: Vec::new(),
+            input: Rc::new(RefCell::new(io::BufReader::new(io::stdin()))),
         }
     }
 
-    pub fn interpret(&mut self, statements: &Vec<Statement>) -> Result<(), InterpreterError> {
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn with_input(mut self, input: Input) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: impl IntoIterator<Item = Capability>) -> Self {
+        self.capabilities.extend(capabilities);
+        self
+    }
+
+    pub fn with_hook(mut self, hook: Box<dyn Hook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    // the file being run, so it can't be imported back into itself
+    pub fn with_script(mut self, filename: &Path) -> Self {
+        if let Some(directory) = filename.parent() {
+            self.directory = directory.to_path_buf();
+        }
+        if let Ok(filename) = filename.canonicalize() {
+            self.modules.insert(filename, None);
+        }
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    // the time limit counts from here, imported modules included
+    pub fn interpret(&mut self, statements: &[Statement]) -> Result<(), RuntimeError> {
+        self.started = Instant::now();
+        self.execute_all(statements)
+    }
+
+    fn execute_all(&mut self, statements: &[Statement]) -> Result<(), RuntimeError> {
         for stmt in statements {
             self.execute(stmt)?;
         }
         Ok(())
     }
 
-    fn evaluate(&mut self, expr: &Expression) -> Result<Object, InterpreterError> {
-        let expr = expr.accept(self)?;
-        Ok(expr)
+    fn define(&mut self, name: &Token, value: Object, constant: bool) -> Result<(), RuntimeError> {
+        let TokenValue::Identifier(key) = &name.value else {
+            unreachable!("Variable name must be an identifier");
+        };
+
+        let mut environment = self.environment.borrow_mut();
+        if self.strict && Rc::ptr_eq(&self.environment, &self.globals) && environment.contains(key)
+        {
+            return Err(RuntimeError::GlobalRedefinition(key.clone(), name.span));
+        }
+
+        if constant {
+            environment.define_constant(key, value);
+        } else {
+            environment.define(key, value);
+        }
+        Ok(())
+    }
+
+    // returns the module at a path relative to the importing file, running
+    // it the first time it is imported
+    fn import(&mut self, path: &Token) -> Result<Object, RuntimeError> {
+        let TokenValue::String(relative) = &path.value else {
+            unreachable!("Module path must be a string");
+        };
+
+        let file = self.directory.join(relative);
+        let Ok(file) = file.canonicalize() else {
+            return Err(RuntimeError::ModuleNotFound(relative.clone(), path.span));
+        };
+        match self.modules.get(&file) {
+            Some(Some(module)) => return Ok(module.clone()),
+            Some(None) => return Err(RuntimeError::ImportCycle(relative.clone(), path.span)),
+            None => {}
+        }
+
+        let Ok(source) = fs::read_to_string(&file) else {
+            return Err(RuntimeError::ModuleNotFound(relative.clone(), path.span));
+        };
+
+        // errors in the module's source are written as they are found, like
+        // they are for the file being run
+        let mut errors = ErrorBag::default();
+        let statements = Parser::new(&mut errors, Lexer::new(&source)).parse();
+        let globals = new_globals();
+        if let Ok(statements) = &statements {
+            let natives = globals.borrow().names();
+            Resolver::new(&mut errors)
+                .with_natives(natives)
+                .with_strict(self.strict)
+                .resolve(statements);
+        }
+        let statements = match statements {
+            Ok(statements) if !errors.has_error() => statements,
+            _ => return Err(RuntimeError::InvalidModule(relative.clone(), path.span)),
+        };
+
+        self.modules.insert(file.clone(), None);
+        let directory = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let previous = (
+            std::mem::replace(&mut self.globals, Rc::clone(&globals)),
+            std::mem::replace(&mut self.environment, Rc::clone(&globals)),
+            std::mem::replace(&mut self.directory, directory),
+        );
+        let result = self.in_frame(format!("<module {}>", relative), |interpreter| {
+            interpreter.execute_all(&statements).map(|_| Object::Nil)
+        });
+        (self.globals, self.environment, self.directory) = previous;
+
+        if let Err(error) = result {
+            self.modules.remove(&file);
+            return Err(error);
+        }
+
+        let name = file.file_stem().unwrap_or_default().to_string_lossy();
+        let module = Object::Module(Rc::new(Module::new(name.into_owned(), globals)));
+        self.modules.insert(file, Some(module.clone()));
+        Ok(module)
     }
 
-    fn execute(&mut self, stmt: &Statement) -> Result<Option<Object>, InterpreterError> {
-        stmt.accept(self)
+    fn evaluate(&mut self, expr: &Expression) -> Result<Object, RuntimeError> {
+        self.nested(expr.span(), |interpreter| expr.accept(interpreter))
+    }
+
+    // every statement and expression that runs inside another takes native
+    // stack, so how deep they go is limited as well as how deep calls go
+    fn nested<T>(
+        &mut self,
+        span: Span,
+        run: impl FnOnce(&mut Self) -> Result<T, RuntimeError>,
+    ) -> Result<T, RuntimeError> {
+        if self.depth >= self.limits.nesting {
+            return Err(RuntimeError::StackOverflow(span));
+        }
+        self.depth += 1;
+        let result = run(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn execute(&mut self, stmt: &Statement) -> Result<Option<Object>, RuntimeError> {
+        self.frames.last_mut().unwrap().line = stmt.line();
+        self.check_limits(stmt.line())?;
+
+        let mut stopped = Ok(());
+        self.run_hooks(|hook, interpreter| {
+            if stopped.is_ok() {
+                stopped = hook.before_statement(interpreter, stmt);
+            }
+        });
+        stopped?;
+        self.nested(stmt.span(), |interpreter| stmt.accept(interpreter))
+    }
+
+    fn check_limits(&mut self, line: usize) -> Result<(), RuntimeError> {
+        let span = Span {
+            line,
+            ..Span::default()
+        };
+
+        self.instructions += 1;
+        if let Some(instructions) = self.limits.instructions {
+            if self.instructions > instructions {
+                return Err(RuntimeError::InstructionLimit(instructions, span));
+            }
+        }
+        if let Some(time) = self.limits.time {
+            if self.started.elapsed() > time {
+                return Err(RuntimeError::TimeLimit(time.as_millis(), span));
+            }
+        }
+        if let Some(heap) = self.limits.heap {
+            if self.environment.borrow().heap() > heap {
+                return Err(RuntimeError::HeapLimit(heap, span));
+            }
+        }
+        Ok(())
+    }
+
+    // runs a call in a new frame, telling the hooks when it starts and returns
+    fn in_frame(
+        &mut self,
+        name: String,
+        call: impl FnOnce(&mut Self) -> Result<Object, RuntimeError>,
+    ) -> Result<Object, RuntimeError> {
+        self.frames.push(Frame::new(name));
+        self.run_hooks(|hook, interpreter| hook.on_call(interpreter));
+
+        let result = call(self);
+
+        self.run_hooks(|hook, interpreter| hook.on_return(interpreter, result.as_ref().ok()));
+        self.frames.pop();
+        result
+    }
+
+    // the hooks are taken out while they run so they can borrow the interpreter
+    fn run_hooks(&mut self, mut run: impl FnMut(&mut dyn Hook, &Self)) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let mut hooks = std::mem::take(&mut self.hooks);
+        for hook in hooks.iter_mut() {
+            run(hook.as_mut(), self);
+        }
+        self.hooks = hooks;
+    }
+
+    // evaluates an expression in another environment, like a default
+    // parameter in the environment of the call
+    pub fn evaluate_in(
+        &mut self,
+        expression: &Expression,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Object, RuntimeError> {
+        let current = std::mem::replace(&mut self.environment, environment);
+        let result = self.evaluate(expression);
+        self.environment = current;
+        result
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn call_site(&self) -> Span {
+        self.call_site
+    }
+
+    // the next line of input without its line ending, None at the end
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.input.borrow_mut().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let length = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(length);
+        Ok(Some(line))
     }
 
     pub fn execute_block(
         &mut self,
         statements: &[Statement],
         environment: Rc<RefCell<Environment>>,
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let current = self.environment.clone();
         self.environment = environment;
 
-        let mut result: Result<Option<Object>, InterpreterError> = Ok(None);
+        let mut result: Result<Option<Object>, RuntimeError> = Ok(None);
         for statement in statements {
             match self.execute(statement) {
                 Ok(Some(value)) => {
@@ -82,19 +384,15 @@ impl Interpreter {
     }
 }
 
-impl ExpressionVisitor<Object, InterpreterError> for Interpreter {
+impl ExpressionVisitor<Object, RuntimeError> for Interpreter {
     fn visit_assignment(
         &mut self,
         name: &Token,
         expression: &Expression,
-    ) -> Result<Object, InterpreterError> {
-        if let TokenValue::Identifier(name) = &name.value {
-            let value = self.evaluate(expression)?;
-            self.environment.borrow_mut().assign(name, value.clone())?;
-            return Ok(value);
-        }
-
-        unreachable!("Assignment name must be an identifier");
+    ) -> Result<Object, RuntimeError> {
+        let value = self.evaluate(expression)?;
+        self.environment.borrow_mut().assign(name, value.clone())?;
+        Ok(value)
     }
 
     fn visit_binary(
@@ -102,49 +400,52 @@ impl ExpressionVisitor<Object, InterpreterError> for Interpreter {
         left: &Expression,
         operator: &Token,
         right: &Expression,
-    ) -> Result<Object, InterpreterError> {
+    ) -> Result<Object, RuntimeError> {
         let left = self.evaluate(left)?;
         let right = self.evaluate(right)?;
 
-        // special case for string concatenation
-        if let (Object::String(left), TokenType::Plus, Object::String(right)) =
-            (&left, operator.token_type, &right)
-        {
-            return Ok(Object::String(format!("{}{}", left, right)));
-        }
-
         match operator.token_type {
-            TokenType::Minus => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Number(left - right))
-            }
-            TokenType::Slash => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Number(left / right))
-            }
-            TokenType::Star => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Number(left * right))
-            }
-            TokenType::Plus => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Number(left + right))
-            }
+            TokenType::Plus => match (&left, &right) {
+                (Object::String(left), Object::String(right)) => {
+                    Ok(Object::String(format!("{}{}", left, right)))
+                }
+                _ if left.as_number().is_none() || right.as_number().is_none() => {
+                    Err(RuntimeError::AddOperands(operator.span))
+                }
+                _ => arithmetic(&left, operator, &right),
+            },
+            TokenType::Minus
+            | TokenType::Slash
+            | TokenType::Star
+            | TokenType::Percent
+            | TokenType::TildeSlash
+            | TokenType::StarStar => arithmetic(&left, operator, &right),
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => bitwise(&left, operator, &right),
             TokenType::Greater => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Boolean(left > right))
+                let ordering = compare(&left, operator, &right)?;
+                Ok(Object::Boolean(ordering == Some(Ordering::Greater)))
             }
             TokenType::GreaterEqual => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Boolean(left >= right))
+                let ordering = compare(&left, operator, &right)?;
+                Ok(Object::Boolean(matches!(
+                    ordering,
+                    Some(Ordering::Greater | Ordering::Equal)
+                )))
             }
             TokenType::Less => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Boolean(left < right))
+                let ordering = compare(&left, operator, &right)?;
+                Ok(Object::Boolean(ordering == Some(Ordering::Less)))
             }
             TokenType::LessEqual => {
-                let (left, right) = check_number_operands(&left, &operator.token_type, &right)?;
-                Ok(Object::Boolean(left <= right))
+                let ordering = compare(&left, operator, &right)?;
+                Ok(Object::Boolean(matches!(
+                    ordering,
+                    Some(Ordering::Less | Ordering::Equal)
+                )))
             }
             TokenType::BangEqual => Ok(Object::Boolean(left != right)),
             TokenType::EqualEqual => Ok(Object::Boolean(left == right)),
@@ -158,8 +459,10 @@ impl ExpressionVisitor<Object, InterpreterError> for Interpreter {
     fn visit_call(
         &mut self,
         callee: &Expression,
+        paren: &Token,
         arguments: &[Expression],
-    ) -> Result<Object, InterpreterError> {
+        named: &[(Token, Expression)],
+    ) -> Result<Object, RuntimeError> {
         let callee = self.evaluate(callee)?;
 
         let mut args = Vec::new();
@@ -167,29 +470,85 @@ impl ExpressionVisitor<Object, InterpreterError> for Interpreter {
             let arg = self.evaluate(argument)?;
             args.push(arg);
         }
+        let mut named_args = Vec::new();
+        for (name, argument) in named {
+            named_args.push((name.clone(), self.evaluate(argument)?));
+        }
 
         if let Object::Callable(callable) = callee {
-            if args.len() != callable.arity() {
-                return Err(InterpreterError::RuntimeError(format!(
-                    "Expected {} arguments but got {}.",
+            // the script is the first frame
+            if self.frames.len() > self.limits.call_depth {
+                return Err(RuntimeError::StackOverflow(paren.span));
+            }
+
+            // with named arguments the function checks what it was given
+            if named_args.is_empty() && !callable.arity().accepts(args.len()) {
+                return Err(RuntimeError::ArityMismatch(
                     callable.arity(),
-                    args.len()
-                )));
+                    args.len(),
+                    paren.span,
+                ));
+            }
+
+            let missing = callable
+                .capabilities()
+                .iter()
+                .find(|capability| !self.capabilities.contains(capability));
+            if let Some(capability) = missing {
+                return Err(RuntimeError::MissingCapability(
+                    callable.to_string(),
+                    *capability,
+                    paren.span,
+                ));
             }
-            return callable.call(self, args);
+
+            self.call_site = paren.span;
+            return self.in_frame(callable.to_string(), |interpreter| {
+                if named_args.is_empty() {
+                    callable.call(interpreter, args)
+                } else {
+                    callable.call_named(interpreter, args, named_args)
+                }
+            });
         }
 
-        Err(InterpreterError::RuntimeError(
-            "Can only call functions and classes.".to_string(),
-        ))
+        Err(RuntimeError::NotCallable(paren.span))
     }
 
-    fn visit_grouping(&mut self, expression: &Expression) -> Result<Object, InterpreterError> {
+    fn visit_conditional(
+        &mut self,
+        condition: &Expression,
+        then_branch: &Expression,
+        else_branch: &Expression,
+    ) -> Result<Object, RuntimeError> {
+        if self.evaluate(condition)?.is_truthy() {
+            self.evaluate(then_branch)
+        } else {
+            self.evaluate(else_branch)
+        }
+    }
+
+    fn visit_get(&mut self, object: &Expression, name: &Token) -> Result<Object, RuntimeError> {
+        match self.evaluate(object)? {
+            Object::Module(module) => module.get(name),
+            _ => Err(RuntimeError::NoProperties(name.span)),
+        }
+    }
+
+    fn visit_grouping(&mut self, expression: &Expression) -> Result<Object, RuntimeError> {
         let value = self.evaluate(expression)?;
         Ok(value)
     }
 
-    fn visit_literal(&mut self, value: &Object) -> Result<Object, InterpreterError> {
+    fn visit_interpolation(&mut self, parts: &[Expression]) -> Result<Object, RuntimeError> {
+        let mut string = String::new();
+        for part in parts {
+            string.push_str(&self.evaluate(part)?.to_string());
+        }
+        Ok(Object::String(string))
+    }
+
+    fn visit_literal(&mut self, value: &Object) -> Result<Object, RuntimeError> {
         Ok(value.clone())
     }
 
@@ -198,14 +557,19 @@ impl ExpressionVisitor<Object, InterpreterError> for Interpreter {
         left: &Expression,
         operator: &Token,
         right: &Expression,
-    ) -> Result<Object, InterpreterError> {
+    ) -> Result<Object, RuntimeError> {
         let left = self.evaluate(left)?;
 
-        if operator.token_type == TokenType::Or {
-            if left.is_truthy() {
-                return Ok(left);
-            }
-        } else if !left.is_truthy() {
+        let short_circuit = match operator.token_type {
+            TokenType::Or => left.is_truthy(),
+            TokenType::And => !left.is_truthy(),
+            TokenType::QuestionQuestion => left != Object::Nil,
+            _ => false,
+        };
+        let branch = if short_circuit { 0 } else { 1 };
+        self.run_hooks(|hook, interpreter| hook.on_branch(interpreter, operator, branch));
+
+        if short_circuit {
             return Ok(left);
         }
 
@@ -216,36 +580,30 @@ impl ExpressionVisitor<Object, InterpreterError> for Interpreter {
         &mut self,
         operator: &Token,
         right: &Expression,
-    ) -> Result<Object, InterpreterError> {
+    ) -> Result<Object, RuntimeError> {
         let right = self.evaluate(right)?;
 
-        match (operator.token_type, &right) {
-            (TokenType::Minus, Object::Number(num)) => {
-                check_number_operand(&operator.token_type, &right)?;
-                Ok(Object::Number(-num))
-            }
-            (TokenType::Bang, _) => Ok(Object::Boolean(!right.is_truthy())),
-            _ => Err(InterpreterError::RuntimeError(format!(
+        match operator.token_type {
+            TokenType::Minus => negate(operator, &right),
+            TokenType::Tilde => complement(operator, &right),
+            TokenType::Bang => Ok(Object::Boolean(!right.is_truthy())),
+            _ => unreachable!(
                 "Invalid unary expression ({} {})",
                 operator.token_type, right
-            ))),
+            ),
         }
     }
 
-    fn visit_variable(&mut self, name: &Token) -> Result<Object, InterpreterError> {
-        if let TokenValue::Identifier(name) = &name.value {
-            let value = self.environment.borrow().get(name)?;
-            return Ok(value.clone());
-        }
-        unreachable!("Variable expression must have a string name");
+    fn visit_variable(&mut self, name: &Token) -> Result<Object, RuntimeError> {
+        self.environment.borrow().get(name)
     }
 }
 
-impl StatementVisitor for Interpreter {
+impl StatementVisitor<Option<Object>, RuntimeError> for Interpreter {
     fn visit_block_statement(
         &mut self,
         statements: &[Statement],
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let current = Some(Rc::clone(&self.environment));
         let environment = Rc::new(RefCell::new(Environment::new(current)));
 
@@ -253,10 +611,20 @@ impl StatementVisitor for Interpreter {
         Ok(result)
     }
 
+    fn visit_constant_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Expression,
+    ) -> Result<Option<Object>, RuntimeError> {
+        let value = self.evaluate(initializer)?;
+        self.define(name, value, true)?;
+        Ok(None)
+    }
+
     fn visit_expression_statement(
         &mut self,
         expr: &Expression,
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let _ = self.evaluate(expr)?;
         Ok(None)
     }
@@ -264,36 +632,32 @@ impl StatementVisitor for Interpreter {
     fn visit_function_statement(
         &mut self,
         name: &Token,
-        params: &[Token],
-        body: &[Statement],
-    ) -> Result<Option<Object>, InterpreterError> {
-        let name = match &name.value {
-            TokenValue::Identifier(name) => name.clone(),
-            _ => unreachable!("Function name must be an identifier"),
-        };
-
+        params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<Option<Object>, RuntimeError> {
         let function = Function::new(
-            name.clone(),
-            params.to_vec(),
-            body.to_vec(),
+            name.value.to_string(),
+            Rc::clone(params),
+            Rc::clone(body),
             Some(self.environment.clone()),
         );
-        self.environment
-            .borrow_mut()
-            .define(name.as_str(), Object::Callable(Box::new(function)));
-
+        self.define(name, Object::Callable(Box::new(function)), false)?;
         Ok(None)
     }
 
     fn visit_if_statement(
         &mut self,
+        keyword: &Token,
         condition: &Expression,
         then_branch: &Statement,
         else_branch: &Option<Box<Statement>>,
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let condition = self.evaluate(condition)?;
         let mut result: Option<Object> = None;
 
+        let branch = if condition.is_truthy() { 0 } else { 1 };
+        self.run_hooks(|hook, interpreter| hook.on_branch(interpreter, keyword, branch));
+
         if condition.is_truthy() {
             result = self.execute(then_branch)?;
         } else if let Some(else_branch) = else_branch {
@@ -303,10 +667,28 @@ impl StatementVisitor for Interpreter {
         Ok(result)
     }
 
-    fn visit_print_statement(
+    fn visit_import_statement(
         &mut self,
-        expr: &Expression,
-    ) -> Result<Option<Object>, InterpreterError> {
+        path: &Token,
+        alias: &Option<Token>,
+        names: &[Token],
+    ) -> Result<Option<Object>, RuntimeError> {
+        let module = self.import(path)?;
+        if let Some(alias) = alias {
+            self.define(alias, module.clone(), false)?;
+        }
+
+        if let Object::Module(module) = &module {
+            for name in names {
+                let value = module.get(name)?;
+                self.define(name, value, false)?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn visit_print_statement(&mut self, expr: &Expression) -> Result<Option<Object>, RuntimeError> {
         let value = self.evaluate(expr)?;
         println!("{}", value);
         Ok(None)
@@ -315,7 +697,7 @@ impl StatementVisitor for Interpreter {
     fn visit_return_statement(
         &mut self,
         value: &Option<Expression>,
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let mut result = Object::Nil;
         if let Some(value_expression) = value {
             result = self.evaluate(value_expression)?;
@@ -327,27 +709,27 @@ impl StatementVisitor for Interpreter {
         &mut self,
         name: &Token,
         initializer: &Option<Expression>,
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let mut value = Object::Nil;
         if initializer.is_some() {
             value = self.evaluate(initializer.as_ref().unwrap())?;
         }
 
-        if let TokenValue::Identifier(name) = &name.value {
-            let name = name.clone();
-            self.environment.borrow_mut().define(name.as_str(), value);
-        }
-
+        self.define(name, value, false)?;
         Ok(None)
     }
 
     fn visit_while_statement(
         &mut self,
+        keyword: &Token,
         condition: &Expression,
         body: &Statement,
-    ) -> Result<Option<Object>, InterpreterError> {
+    ) -> Result<Option<Object>, RuntimeError> {
         let mut value = self.evaluate(condition)?;
         loop {
+            let branch = if value.is_truthy() { 0 } else { 1 };
+            self.run_hooks(|hook, interpreter| hook.on_branch(interpreter, keyword, branch));
+
             if !value.is_truthy() {
                 break;
             }
@@ -363,8 +745,328 @@ impl StatementVisitor for Interpreter {
     }
 }
 
+// a global environment with the native functions defined
+fn new_globals() -> Rc<RefCell<Environment>> {
+    let globals = Rc::new(RefCell::new(Environment::default()));
+    let natives: [(&str, Box<dyn Callable>); 11] = [
+        ("args", Box::new(Args {})),
+        ("at", Box::new(At {})),
+        ("clock", Box::new(Clock {})),
+        ("exit", Box::new(Exit {})),
+        ("len", Box::new(Len {})),
+        ("list_dir", Box::new(ListDir {})),
+        ("max", Box::new(Extreme::max())),
+        ("min", Box::new(Extreme::min())),
+        ("read_file", Box::new(ReadFile {})),
+        ("read_line", Box::new(ReadLine {})),
+        ("write_file", Box::new(WriteFile {})),
+    ];
+    for (name, native) in natives {
+        globals.borrow_mut().define(name, Object::Callable(native));
+    }
+    globals
+}
+
 impl Default for Interpreter {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(directory: &Path, source: &str) -> Result<Interpreter, RuntimeError> {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+        let mut interpreter = Interpreter::new().with_script(&directory.join("main.lox"));
+        interpreter.interpret(&statements)?;
+        Ok(interpreter)
+    }
+
+    fn global(interpreter: &Interpreter, name: &str) -> Object {
+        let name = Token::new_identifier(1, name.to_string());
+        interpreter.globals.borrow().get(&name).unwrap()
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let source = "var a = 1; var s = \"a ${a + 1} ${nil} ${1.5}!\";";
+        let interpreter = run(&std::env::temp_dir(), source).unwrap();
+        assert_eq!(
+            global(&interpreter, "s"),
+            Object::String("a 2 nil 1.5!".to_string())
+        );
+    }
+
+    // hit() counts how often it runs, so a skipped side shows as no hits
+    #[rstest::rstest]
+    #[case("false ?? hit()", Object::Boolean(false), 0)]
+    #[case("0 ?? hit()", Object::Integer(0), 0)]
+    #[case("nil ?? hit()", Object::Integer(2), 1)]
+    #[case("nil ?? nil ?? hit()", Object::Integer(2), 1)]
+    #[case("true ? 1 : hit()", Object::Integer(1), 0)]
+    #[case("false ? hit() : 3", Object::Integer(3), 0)]
+    #[case("nil ? hit() : 3", Object::Integer(3), 0)]
+    fn test_conditional_operators(
+        #[case] expression: &str,
+        #[case] expected: Object,
+        #[case] hits: i64,
+    ) {
+        let source = format!(
+            "var hits = 0; fun hit() {{ hits = hits + 1; return 2; }} var r = {};",
+            expression
+        );
+        let interpreter = run(&std::env::temp_dir(), &source).unwrap();
+        assert_eq!(global(&interpreter, "r"), expected);
+        assert_eq!(global(&interpreter, "hits"), Object::Integer(hits));
+    }
+
+    #[test]
+    fn test_imports() {
+        let directory = std::env::temp_dir().join(format!("lox-imports-{}", std::process::id()));
+        fs::create_dir_all(directory.join("lib")).unwrap();
+        fs::write(
+            directory.join("lib/a.lox"),
+            "var x = 1; fun f() { return x; }",
+        )
+        .unwrap();
+        fs::write(directory.join("lib/b.lox"), "import \"a.lox\" as a;").unwrap();
+        fs::write(directory.join("c.lox"), "import \"d.lox\" as d;").unwrap();
+        fs::write(directory.join("d.lox"), "import \"c.lox\" as c;").unwrap();
+
+        let source = "import \"lib/a.lox\" as a; import \"lib/b.lox\" as b; \
+                      from \"lib/a.lox\" import f; var same = a == b.a; var y = f();";
+        let interpreter = run(&directory, source).unwrap();
+        assert_eq!(global(&interpreter, "same"), Object::Boolean(true));
+        assert_eq!(global(&interpreter, "y"), Object::Integer(1));
+
+        assert!(matches!(
+            run(&directory, "import \"c.lox\" as c;").err(),
+            Some(RuntimeError::ImportCycle(_, _))
+        ));
+        assert!(matches!(
+            run(&directory, "import \"missing.lox\" as m;").err(),
+            Some(RuntimeError::ModuleNotFound(_, _))
+        ));
+        assert!(matches!(
+            run(&directory, "from \"lib/a.lox\" import z;").err(),
+            Some(RuntimeError::UndefinedProperty(_, _))
+        ));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[rstest::rstest]
+    #[case("fun f() { f(); } f();", Limits { call_depth: 8, ..Limits::default() }, "Stack overflow.")]
+    #[case("while (true) {}", Limits { instructions: Some(100), ..Limits::default() }, "Instruction limit of 100 exceeded.")]
+    #[case("while (true) {}", Limits { time: Some(std::time::Duration::from_millis(10)), ..Limits::default() }, "Time limit of 10 ms exceeded.")]
+    #[case("var s = \"x\"; while (true) s = s + s;", Limits { heap: Some(1000), ..Limits::default() }, "Memory limit of 1000 bytes exceeded.")]
+    fn test_limits(#[case] source: &str, #[case] limits: Limits, #[case] message: &str) {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+        let error = Interpreter::new()
+            .with_limits(limits)
+            .interpret(&statements)
+            .unwrap_err();
+        assert_eq!(error.to_string(), message);
+    }
+
+    // each half waits 30 ms, so only the whole run is over the 50 ms limit
+    #[test]
+    fn test_time_limit_includes_imports() {
+        let directory = std::env::temp_dir().join(format!("lox-timeout-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let wait = "var start = clock(); while (clock() - start < 0.03) {}";
+        fs::write(directory.join("m.lox"), wait).unwrap();
+
+        let source = format!("{} import \"m.lox\" as m;", wait);
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(&source))
+            .parse()
+            .unwrap();
+        let result = Interpreter::new()
+            .with_script(&directory.join("main.lox"))
+            .with_capabilities([Capability::Time])
+            .with_limits(Limits {
+                time: Some(std::time::Duration::from_millis(50)),
+                ..Limits::default()
+            })
+            .interpret(&statements);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(matches!(result, Err(RuntimeError::TimeLimit(_, _))));
+    }
+
+    // blocks inside each call use stack the call depth doesn't count, which
+    // the nesting limit has to catch before the stack main gives us runs out
+    #[test]
+    fn test_nesting_limit() {
+        let (a, error) = std::thread::Builder::new()
+            .stack_size(limits::STACK_SIZE)
+            .spawn(|| {
+                let source = "fun f(n) { if (n == 0) return 0; {{{{{{{{{{ return 1 + f(n - 1); }}}}}}}}}} }\n\
+                              var a = f(250);\n\
+                              var b = f(100000);";
+                let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+                let statements = Parser::new(&mut errors, Lexer::new(source))
+                    .parse()
+                    .unwrap();
+                let mut interpreter = Interpreter::new().with_limits(Limits {
+                    call_depth: 1_000_000,
+                    ..Limits::default()
+                });
+                let error = interpreter.interpret(&statements).unwrap_err();
+                (global(&interpreter, "a").to_string(), error)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(a, "250");
+        assert!(matches!(error, RuntimeError::StackOverflow(_)));
+    }
+
+    #[test]
+    fn test_heap_is_freed() {
+        let source =
+            "fun f() { var s = \"xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx\"; } \
+                      for (var i = 0; i < 100; i = i + 1) f();";
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+        let mut interpreter = Interpreter::new().with_limits(Limits {
+            heap: Some(1000),
+            ..Limits::default()
+        });
+        interpreter.interpret(&statements).unwrap();
+        assert!(interpreter.globals.borrow().heap() < 1000);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new("var t = clock();"))
+            .parse()
+            .unwrap();
+
+        let error = Interpreter::new().interpret(&statements).unwrap_err();
+        assert!(matches!(
+            error,
+            RuntimeError::MissingCapability(_, Capability::Time, _)
+        ));
+        assert_eq!(error.to_string(), "<fn clock> needs the 'time' capability.");
+
+        Interpreter::new()
+            .with_capabilities([Capability::Time])
+            .interpret(&statements)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_io_natives() {
+        let directory = std::env::temp_dir().join(format!("lox-io-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("out.txt").to_string_lossy().into_owned();
+
+        let source = format!(
+            "var n = len(args()); var first = at(args(), 0); \
+             var line = read_line(); var end = read_line(); \
+             write_file(\"{path}\", line); var read = read_file(\"{path}\"); \
+             var names = list_dir(\"{}\"); exit(2);",
+            directory.to_string_lossy()
+        );
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(&source))
+            .parse()
+            .unwrap();
+        let mut interpreter = Interpreter::new()
+            .with_capabilities(Capability::ALL)
+            .with_args(vec!["a".to_string(), "b".to_string()])
+            .with_input(Rc::new(RefCell::new("hello\r\n".as_bytes())));
+        let error = interpreter.interpret(&statements).unwrap_err();
+        assert!(matches!(error, RuntimeError::Exit(2, _)));
+
+        assert_eq!(global(&interpreter, "n"), Object::Integer(2));
+        assert_eq!(
+            global(&interpreter, "first"),
+            Object::String("a".to_string())
+        );
+        assert_eq!(global(&interpreter, "end"), Object::Nil);
+        assert_eq!(
+            global(&interpreter, "read"),
+            Object::String("hello".to_string())
+        );
+        assert_eq!(global(&interpreter, "names").to_string(), "[out.txt]");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[rstest::rstest]
+    #[case("fun f(a, b = a * 2) { return b; } var x = f(1);", Object::Integer(2))]
+    #[case(
+        "fun f(a, b = a * 2) { return b; } var x = f(1, 5);",
+        Object::Integer(5)
+    )]
+    #[case(
+        "fun f(a, ...rest) { return len(rest); } var x = f(1, 2, 3);",
+        Object::Integer(2)
+    )]
+    #[case(
+        "fun f(...rest) { return rest; } var x = len(f());",
+        Object::Integer(0)
+    )]
+    #[case("var x = max(1, 3.5, 2);", Object::Number(3.5))]
+    #[case("var x = min(4, 2, 3);", Object::Integer(2))]
+    #[case(
+        "fun f(a, b) { return a - b; } var x = f(b: 1, a: 3);",
+        Object::Integer(2)
+    )]
+    #[case(
+        "fun f(a, b = 2, c = 3) { return a + c; } var x = f(1, c: 5);",
+        Object::Integer(6)
+    )]
+    #[case(
+        "fun f(a, ...r) { return len(r); } var x = f(a: 1);",
+        Object::Integer(0)
+    )]
+    fn test_optional_parameters(#[case] source: &str, #[case] expected: Object) {
+        let interpreter = run(Path::new("."), source).unwrap();
+        assert_eq!(global(&interpreter, "x"), expected);
+    }
+
+    #[rstest::rstest]
+    #[case("fun f(a, b = 1) {} f();", "Expected 1 or 2 arguments but got 0.")]
+    #[case(
+        "fun f(a, b = 1) {} f(1, 2, 3);",
+        "Expected 1 or 2 arguments but got 3."
+    )]
+    #[case("fun f(a, ...b) {} f();", "Expected at least 1 argument but got 0.")]
+    #[case(
+        "fun f(a, b, ...c) {} f();",
+        "Expected at least 2 arguments but got 0."
+    )]
+    #[case(
+        "fun f(a, b = 1, c = 2) {} f();",
+        "Expected 1 to 3 arguments but got 0."
+    )]
+    #[case("fun f(a = 1) {} f(1, 2);", "Expected 0 or 1 argument but got 2.")]
+    #[case("fun f(a, b) {} f(1);", "Expected 2 arguments but got 1.")]
+    #[case("max();", "Expected at least 1 argument but got 0.")]
+    #[case("fun f(a) {} f(b: 1);", "Unknown argument 'b'.")]
+    #[case("fun f(a) {} f(1, a: 2);", "Argument 'a' was given more than once.")]
+    #[case("fun f(a) {} f(a: 1, a: 2);", "Argument 'a' was given more than once.")]
+    #[case("fun f(a, b) {} f(b: 1);", "Missing argument 'a'.")]
+    #[case("fun f(a) {} f(1, 2, a: 3);", "Expected 1 argument but got 3.")]
+    #[case("max(a: 1);", "<fn max> doesn't take named arguments.")]
+    fn test_arity_errors(#[case] source: &str, #[case] message: &str) {
+        let error = run(Path::new("."), source).err().unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
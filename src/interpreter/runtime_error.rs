@@ -0,0 +1,142 @@
+use crate::token::span::Span;
+
+use super::capability::Capability;
+use crate::parser::callable::Arity;
+
+// Display gives the bare message, the span is where the error is reported
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    #[error("Operand must be a number.")]
+    NumberOperand(Span),
+
+    #[error("Operands must be numbers.")]
+    NumberOperands(Span),
+
+    #[error("Operands must be two numbers or two strings.")]
+    AddOperands(Span),
+
+    #[error("Undefined variable '{0}'.")]
+    UndefinedVariable(String, Span),
+
+    #[error("Can only call functions and classes.")]
+    NotCallable(Span),
+
+    #[error("Expected {0} but got {1}.")]
+    ArityMismatch(Arity, usize, Span),
+
+    #[error("Integer overflow.")]
+    IntegerOverflow(Span),
+
+    #[error("Division by zero.")]
+    DivisionByZero(Span),
+
+    #[error("Operand must be an integer.")]
+    IntegerOperand(Span),
+
+    #[error("Operands must be integers.")]
+    IntegerOperands(Span),
+
+    #[error("Shift amount must be between 0 and 63, got {0}.")]
+    InvalidShift(i64, Span),
+
+    #[error("Can't assign to constant '{0}'.")]
+    ConstantAssignment(String, Span),
+
+    #[error("Global '{0}' is already defined.")]
+    GlobalRedefinition(String, Span),
+
+    #[error("Can't find module '{0}'.")]
+    ModuleNotFound(String, Span),
+
+    #[error("Import cycle through module '{0}'.")]
+    ImportCycle(String, Span),
+
+    #[error("Module '{0}' has errors.")]
+    InvalidModule(String, Span),
+
+    #[error("Only modules have properties.")]
+    NoProperties(Span),
+
+    #[error("Undefined property '{0}'.")]
+    UndefinedProperty(String, Span),
+
+    #[error("Stack overflow.")]
+    StackOverflow(Span),
+
+    #[error("Instruction limit of {0} exceeded.")]
+    InstructionLimit(u64, Span),
+
+    #[error("Time limit of {0} ms exceeded.")]
+    TimeLimit(u128, Span),
+
+    #[error("Memory limit of {0} bytes exceeded.")]
+    HeapLimit(usize, Span),
+
+    #[error("{0} needs the '{1}' capability.")]
+    MissingCapability(String, Capability, Span),
+
+    // the native, which argument and what it should have been
+    #[error("Argument {1} to {0} must be {2}.")]
+    ArgumentType(String, usize, &'static str, Span),
+
+    // what was being done, the path and why it failed
+    #[error("Can't {0} '{1}': {2}.")]
+    Io(&'static str, String, String, Span),
+
+    #[error("Index {0} is out of range.")]
+    IndexOutOfRange(i64, Span),
+
+    #[error("Unknown argument '{0}'.")]
+    UnknownArgument(String, Span),
+
+    #[error("Argument '{0}' was given more than once.")]
+    DuplicateArgument(String, Span),
+
+    #[error("Missing argument '{0}'.")]
+    MissingArgument(String, Span),
+
+    #[error("{0} doesn't take named arguments.")]
+    NamedArguments(String, Span),
+
+    // not a failure, exit() unwinds the program with it
+    #[error("Exited with code {0}.")]
+    Exit(i32, Span),
+}
+
+impl RuntimeError {
+    pub fn span(&self) -> Span {
+        match self {
+            RuntimeError::NumberOperand(span)
+            | RuntimeError::NumberOperands(span)
+            | RuntimeError::AddOperands(span)
+            | RuntimeError::UndefinedVariable(_, span)
+            | RuntimeError::NotCallable(span)
+            | RuntimeError::ArityMismatch(_, _, span)
+            | RuntimeError::IntegerOverflow(span)
+            | RuntimeError::DivisionByZero(span)
+            | RuntimeError::IntegerOperand(span)
+            | RuntimeError::IntegerOperands(span)
+            | RuntimeError::InvalidShift(_, span)
+            | RuntimeError::ConstantAssignment(_, span)
+            | RuntimeError::GlobalRedefinition(_, span)
+            | RuntimeError::ModuleNotFound(_, span)
+            | RuntimeError::ImportCycle(_, span)
+            | RuntimeError::InvalidModule(_, span)
+            | RuntimeError::NoProperties(span)
+            | RuntimeError::UndefinedProperty(_, span)
+            | RuntimeError::StackOverflow(span)
+            | RuntimeError::InstructionLimit(_, span)
+            | RuntimeError::TimeLimit(_, span)
+            | RuntimeError::HeapLimit(_, span)
+            | RuntimeError::MissingCapability(_, _, span)
+            | RuntimeError::ArgumentType(_, _, _, span)
+            | RuntimeError::Io(_, _, _, span)
+            | RuntimeError::IndexOutOfRange(_, span)
+            | RuntimeError::UnknownArgument(_, span)
+            | RuntimeError::DuplicateArgument(_, span)
+            | RuntimeError::MissingArgument(_, span)
+            | RuntimeError::NamedArguments(_, span)
+            | RuntimeError::Exit(_, span) => *span,
+        }
+    }
+}
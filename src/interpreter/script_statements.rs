@@ -0,0 +1,92 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    parser::{
+        expression::Expression,
+        statement::{Parameter, Statement},
+    },
+    token::Token,
+    visitor::statement_visitor::StatementVisitor,
+};
+
+// The statements of a script, function bodies included, keyed by address.
+// Hooks use it to tell the script's statements from those of the modules it
+// imports, which share line numbers with it. Blocks aren't recorded, their
+// line is only where the braces are.
+#[derive(Debug, Default)]
+pub struct ScriptStatements {
+    lines: HashMap<usize, usize>,
+}
+
+impl ScriptStatements {
+    pub fn new(statements: &[Statement]) -> Self {
+        let mut script = Self::default();
+        script.statements(statements);
+        script
+    }
+
+    // the line of a statement of the script, None for any other statement
+    pub fn line(&self, statement: &Statement) -> Option<usize> {
+        self.lines.get(&(statement as *const _ as usize)).copied()
+    }
+
+    // every line with a statement on it, in no particular order
+    pub fn lines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lines.values().copied()
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        if !matches!(statement, Statement::Block(_, _)) {
+            self.lines
+                .insert(statement as *const _ as usize, statement.line());
+        }
+        let _ = statement.accept(self);
+    }
+}
+
+impl StatementVisitor<(), ()> for ScriptStatements {
+    fn visit_block_statement(&mut self, statements: &[Statement]) -> Result<(), ()> {
+        self.statements(statements);
+        Ok(())
+    }
+
+    fn visit_function_statement(
+        &mut self,
+        _name: &Token,
+        _params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<(), ()> {
+        self.statements(body);
+        Ok(())
+    }
+
+    fn visit_if_statement(
+        &mut self,
+        _keyword: &Token,
+        _condition: &Expression,
+        then_branch: &Statement,
+        else_branch: &Option<Box<Statement>>,
+    ) -> Result<(), ()> {
+        self.statement(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.statement(else_branch);
+        }
+        Ok(())
+    }
+
+    fn visit_while_statement(
+        &mut self,
+        _keyword: &Token,
+        _condition: &Expression,
+        body: &Statement,
+    ) -> Result<(), ()> {
+        self.statement(body);
+        Ok(())
+    }
+}
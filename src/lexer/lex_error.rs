@@ -0,0 +1,44 @@
+use crate::token::span::Span;
+
+// Display gives the bare message, LoxError adds the "[line n] Error[code]: " prefix
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LexError {
+    #[error("Unexpected character: {0}")]
+    UnexpectedCharacter(char, Span),
+    #[error("Unterminated string.")]
+    UnterminatedString(Span),
+    #[error("Unterminated string interpolation.")]
+    UnterminatedInterpolation(Span),
+    #[error("Unterminated block comment.")]
+    UnterminatedBlockComment(Span),
+    #[error("Invalid escape sequence: \\{0}")]
+    InvalidEscape(char, Span),
+    #[error("Invalid unicode escape sequence: \\u{{{0}")]
+    InvalidUnicodeEscape(String, Span),
+    // the kind is "integer", "number", "hexadecimal" or "binary"
+    #[error("Invalid {0} literal: {1}")]
+    InvalidNumber(&'static str, String, Span),
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedCharacter(_, span)
+            | LexError::UnterminatedString(span)
+            | LexError::UnterminatedInterpolation(span)
+            | LexError::UnterminatedBlockComment(span)
+            | LexError::InvalidEscape(_, span)
+            | LexError::InvalidUnicodeEscape(_, span)
+            | LexError::InvalidNumber(_, _, span) => *span,
+        }
+    }
+
+    // the line the error is reported on
+    pub fn line(&self) -> usize {
+        match self {
+            // like jlox, a string that runs off the end is reported where the source ends
+            LexError::UnterminatedString(span) => span.end_line,
+            error => error.span().line,
+        }
+    }
+}
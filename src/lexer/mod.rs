@@ -1,20 +1,32 @@
-use anyhow::Result;
-use std::io::Write;
+pub mod lex_error;
 
-use crate::{
-    error_bag::ErrorBag,
-    token::{token_type::TokenType, Token},
-    InterpreterError,
-};
+use std::collections::VecDeque;
+use unicode_xid::UnicodeXID;
+
+use lex_error::LexError;
+
+use crate::token::{span::Span, token_type::TokenType, Token};
+
+// Lexes on demand, one token at a time. Scanning a single lexeme can produce
+// more than one item, e.g. a string with a bad escape yields an error and the
+// string token, so items wait in `pending` until they are asked for.
 
 pub struct Lexer<'a> {
     text: &'a str,
     line: usize,
-    pos: Option<usize>,
-    iter: std::iter::Peekable<std::str::Chars<'a>>,
+    column: usize,
+    // byte offset of the next character to be read
+    current: usize,
+    // where the token currently being scanned began
+    start: usize,
+    start_line: usize,
+    start_column: usize,
+    // open braces inside each string interpolation that is being lexed
+    interpolations: Vec<usize>,
+    trivia: bool,
     keywords: std::collections::HashMap<&'static str, TokenType>,
-    tokens: Vec<Token>,
-    errors: &'a mut ErrorBag,
+    pending: VecDeque<Result<Token, LexError>>,
+    finished: bool,
 }
 
 fn is_digit(chr: Option<char>) -> bool {
@@ -24,24 +36,42 @@ fn is_digit(chr: Option<char>) -> bool {
     }
 }
 
+// digits in the given radix, with "_" only allowed between two digits
+fn is_valid_digits(digits: &str, radix: u32) -> bool {
+    !digits.is_empty()
+        && !digits.starts_with('_')
+        && !digits.ends_with('_')
+        && !digits.contains("__")
+        && digits.chars().all(|chr| chr == '_' || chr.is_digit(radix))
+}
+
 impl<'a> Lexer<'a> {
-    pub fn new(errors: &'a mut ErrorBag, text: &'a str) -> Self {
+    pub fn new(text: &'a str) -> Self {
         Self {
             text,
             line: 1,
-            pos: None,
-            iter: text.chars().peekable(),
-            tokens: Vec::new(),
-            errors,
+            column: 1,
+            current: 0,
+            start: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
+            trivia: false,
+            pending: VecDeque::new(),
+            finished: false,
             // there must be a better way to do this
             keywords: std::collections::HashMap::from([
                 ("and", TokenType::And),
+                ("as", TokenType::As),
                 ("class", TokenType::Class),
+                ("const", TokenType::Const),
                 ("else", TokenType::Else),
                 ("false", TokenType::False),
                 ("for", TokenType::For),
+                ("from", TokenType::From),
                 ("fun", TokenType::Fun),
                 ("if", TokenType::If),
+                ("import", TokenType::Import),
                 ("nil", TokenType::Nil),
                 ("or", TokenType::Or),
                 ("print", TokenType::Print),
@@ -55,82 +85,131 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    pub fn tokenize(&mut self, print_tokens: bool) -> Result<Vec<Token>, InterpreterError> {
-        let mut lexical_failure = false;
-
-        while let Some(chr) = self.advance() {
-            match chr {
-                // grouping tokens
-                '(' => self.add_token(Token::from_token_type(self.line, TokenType::LeftParen)),
-                ')' => self.add_token(Token::from_token_type(self.line, TokenType::RightParen)),
-                '{' => self.add_token(Token::from_token_type(self.line, TokenType::LeftBrace)),
-                '}' => self.add_token(Token::from_token_type(self.line, TokenType::RightBrace)),
-                // separator tokens
-                ',' => self.add_token(Token::from_token_type(self.line, TokenType::Comma)),
-                '.' => self.add_token(Token::from_token_type(self.line, TokenType::Dot)),
-                ';' => self.add_token(Token::from_token_type(self.line, TokenType::Semicolon)),
-                // arithmetic tokens
-                '-' => self.add_token(Token::from_token_type(self.line, TokenType::Minus)),
-                '+' => self.add_token(Token::from_token_type(self.line, TokenType::Plus)),
-                '*' => self.add_token(Token::from_token_type(self.line, TokenType::Star)),
-                '/' => {
-                    if self.peek() == Some('/') {
-                        while self.peek() != Some('\n') && self.peek().is_some() {
-                            self.advance();
-                        }
-                    } else {
-                        self.add_token(Token::from_token_type(self.line, TokenType::Slash));
-                    }
+    // keep comments and whitespace as tokens, for tools that need the full source
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    fn scan_token(&mut self) {
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column;
+
+        let Some(chr) = self.advance() else {
+            if !self.interpolations.is_empty() {
+                self.interpolations.clear();
+                self.error(LexError::UnterminatedInterpolation(self.lexeme_span()));
+            }
+
+            self.add_token(Token::new_eof(self.line));
+            self.finished = true;
+            return;
+        };
+
+        match chr {
+            // grouping tokens
+            '(' => self.add_token(Token::from_token_type(self.line, TokenType::LeftParen)),
+            ')' => self.add_token(Token::from_token_type(self.line, TokenType::RightParen)),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
                 }
-                // comparison tokens
-                '=' => self.add_multichar_token('=', TokenType::EqualEqual, TokenType::Equal),
-                '!' => self.add_multichar_token('=', TokenType::BangEqual, TokenType::Bang),
-                '<' => self.add_multichar_token('=', TokenType::LessEqual, TokenType::Less),
-                '>' => self.add_multichar_token('=', TokenType::GreaterEqual, TokenType::Greater),
-                // identifiers
-                // strings
-                '"' => {
-                    if !self.string() {
-                        lexical_failure = true;
-                    }
+                self.add_token(Token::from_token_type(self.line, TokenType::LeftBrace))
+            }
+            '}' => match self.interpolations.last_mut() {
+                // this brace closes an interpolated expression, the string carries on
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string();
                 }
-                unmatched => {
-                    if unmatched.is_whitespace() {
-                        continue;
-                    }
-                    // numbers
-                    if unmatched.is_ascii_digit() && self.number().is_ok() {
-                        continue;
-                    }
-                    // identifiers and keywords
-                    if (unmatched.is_ascii_alphabetic() || unmatched == '_')
-                        && self.identifier().is_ok()
-                    {
-                        continue;
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_token(Token::from_token_type(self.line, TokenType::RightBrace))
+                }
+                None => self.add_token(Token::from_token_type(self.line, TokenType::RightBrace)),
+            },
+            // separator tokens
+            ',' => self.add_token(Token::from_token_type(self.line, TokenType::Comma)),
+            '.' => {
+                // leading-dot numbers like .5
+                if is_digit(self.peek()) {
+                    self.number();
+                } else if self.peek() == Some('.') && self.peek_to(1) == Some('.') {
+                    self.advance();
+                    self.advance();
+                    self.add_token(Token::from_token_type(self.line, TokenType::DotDotDot))
+                } else {
+                    self.add_token(Token::from_token_type(self.line, TokenType::Dot))
+                }
+            }
+            ';' => self.add_token(Token::from_token_type(self.line, TokenType::Semicolon)),
+            ':' => self.add_token(Token::from_token_type(self.line, TokenType::Colon)),
+            // conditional tokens
+            '?' => self.add_multichar_token('?', TokenType::QuestionQuestion, TokenType::Question),
+            // arithmetic tokens
+            '-' => self.add_multichar_token('=', TokenType::MinusEqual, TokenType::Minus),
+            '+' => self.add_multichar_token('=', TokenType::PlusEqual, TokenType::Plus),
+            '*' => {
+                if self.match_char('*') {
+                    self.add_token(Token::from_token_type(self.line, TokenType::StarStar))
+                } else {
+                    self.add_multichar_token('=', TokenType::StarEqual, TokenType::Star)
+                }
+            }
+            '%' => self.add_token(Token::from_token_type(self.line, TokenType::Percent)),
+            // bitwise tokens
+            '~' => self.add_multichar_token('/', TokenType::TildeSlash, TokenType::Tilde),
+            '&' => self.add_token(Token::from_token_type(self.line, TokenType::Ampersand)),
+            '^' => self.add_token(Token::from_token_type(self.line, TokenType::Caret)),
+            '|' => self.add_token(Token::from_token_type(self.line, TokenType::Pipe)),
+            '/' => {
+                if self.peek() == Some('/') {
+                    while self.peek() != Some('\n') && self.peek().is_some() {
+                        self.advance();
                     }
-
-                    self.errors.report_lex_error(
-                        self.line,
-                        &format!("Unexpected character: {}", unmatched),
-                    );
-                    lexical_failure = true;
+                    self.add_trivia(TokenType::Comment);
+                } else if self.peek() == Some('*') {
+                    self.block_comment();
+                } else {
+                    self.add_multichar_token('=', TokenType::SlashEqual, TokenType::Slash);
                 }
             }
-        }
-
-        self.add_token(Token::new_eof(self.line));
-
-        if print_tokens {
-            for token in &self.tokens {
-                token.print(std::io::stdout().by_ref()).unwrap();
+            // comparison tokens
+            '=' => self.add_multichar_token('=', TokenType::EqualEqual, TokenType::Equal),
+            '!' => self.add_multichar_token('=', TokenType::BangEqual, TokenType::Bang),
+            '<' => {
+                if self.match_char('<') {
+                    self.add_token(Token::from_token_type(self.line, TokenType::LessLess))
+                } else {
+                    self.add_multichar_token('=', TokenType::LessEqual, TokenType::Less)
+                }
+            }
+            '>' => {
+                if self.match_char('>') {
+                    self.add_token(Token::from_token_type(self.line, TokenType::GreaterGreater))
+                } else {
+                    self.add_multichar_token('=', TokenType::GreaterEqual, TokenType::Greater)
+                }
+            }
+            '"' => {
+                self.string();
+            }
+            unmatched => {
+                if unmatched.is_whitespace() {
+                    while self.peek().is_some_and(char::is_whitespace) {
+                        self.advance();
+                    }
+                    self.add_trivia(TokenType::Whitespace);
+                } else if unmatched.is_ascii_digit() {
+                    self.number();
+                } else if unmatched.is_xid_start() || unmatched == '_' {
+                    self.identifier();
+                } else {
+                    self.error(LexError::UnexpectedCharacter(unmatched, self.lexeme_span()));
+                }
             }
         }
-
-        if lexical_failure {
-            return Err(InterpreterError::LexicalFailure);
-        }
-
-        Ok(self.tokens.clone())
     }
 
     fn add_multichar_token(&mut self, chr: char, double: TokenType, single: TokenType) {
@@ -141,50 +220,63 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn add_token(&mut self, token: Token) {
-        self.tokens.push(token);
+    fn add_token(&mut self, mut token: Token) {
+        if token.lexeme.is_none() {
+            token.lexeme = Some(self.get_lexeme(self.start, self.current).to_string());
+        }
+
+        let span = self.lexeme_span();
+        self.pending.push_back(Ok(token.with_span(span)));
+    }
+
+    fn add_trivia(&mut self, token_type: TokenType) {
+        if self.trivia {
+            self.add_token(Token::from_token_type(self.line, token_type));
+        }
+    }
+
+    fn error(&mut self, error: LexError) {
+        self.pending.push_back(Err(error));
+    }
+
+    // from the given position up to the next character to be read
+    fn span_from(&self, start: usize, position: (usize, usize)) -> Span {
+        Span::new(start, self.current, position, (self.line, self.column))
+    }
+
+    fn lexeme_span(&self) -> Span {
+        self.span_from(self.start, (self.start_line, self.start_column))
     }
 
     fn advance(&mut self) -> Option<char> {
-        let chr = self.iter.next()?;
+        let chr = self.peek()?;
+        self.current += chr.len_utf8();
 
         if chr == '\n' {
             self.line += 1;
-        }
-
-        match self.pos {
-            Some(pos) => self.pos = Some(pos + 1),
-            None => self.pos = Some(0),
+            self.column = 1;
+        } else {
+            self.column += 1;
         }
 
         Some(chr)
     }
 
-    fn get_lexeme(&self, start: usize, end: usize) -> String {
-        self.text
-            .chars()
-            .skip(start)
-            .take(end - start + 1)
-            .collect()
+    fn get_lexeme(&self, start: usize, end: usize) -> &'a str {
+        &self.text[start..end]
     }
 
-    fn identifier(&mut self) -> Result<(), InterpreterError> {
-        let start = self.pos();
-        while self
-            .peek()
-            .map_or(false, |chr| chr.is_ascii_alphanumeric() || chr == '_')
-        {
+    fn identifier(&mut self) {
+        while self.peek().is_some_and(UnicodeXID::is_xid_continue) {
             self.advance();
         }
 
-        let lexeme = self.get_lexeme(start, self.pos());
-        if let Some(keyword) = self.keywords.get(lexeme.as_str()) {
+        let lexeme = self.get_lexeme(self.start, self.current);
+        if let Some(keyword) = self.keywords.get(lexeme) {
             self.add_token(Token::from_token_type(self.line, *keyword));
         } else {
-            self.add_token(Token::new_identifier(self.line, lexeme));
+            self.add_token(Token::new_identifier(self.line, lexeme.to_string()));
         }
-
-        Ok(())
     }
 
     fn match_char(&mut self, expected: char) -> bool {
@@ -196,62 +288,518 @@ impl<'a> Lexer<'a> {
         false
     }
 
-    fn number(&mut self) -> Result<(), InterpreterError> {
-        let start = self.pos();
-        while is_digit(self.peek()) {
-            self.advance();
+    // 123, 1_000, 1.5, .5, 1e-9, 0xFF and 0b1010. Literals without a fraction
+    // or an exponent are integers, unless they are too big for an i64.
+    fn number(&mut self) {
+        let start = self.start;
+        let first = self.peek_at(start);
+
+        if first == Some('0') && matches!(self.peek(), Some('x' | 'X' | 'b' | 'B')) {
+            return self.radix_number(start);
         }
 
-        // look for a fractional part
-        if self.peek() == Some('.') && is_digit(self.peek_to(1)) {
-            // consume the "."
-            self.advance();
+        let mut fraction = first == Some('.');
+        if !fraction {
+            self.decimal_digits();
 
-            while is_digit(self.peek()) {
+            // look for a fractional part
+            if self.peek() == Some('.') && is_digit(self.peek_to(1)) {
+                // consume the "."
                 self.advance();
+                fraction = true;
             }
         }
 
-        let lexeme = self.get_lexeme(start, self.pos());
-        let value = lexeme.parse::<f64>().unwrap();
-        self.add_token(Token::new_number(self.line, lexeme, value));
+        if fraction {
+            self.decimal_digits();
+        }
+
+        // look for an exponent
+        let mut exponent = false;
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let signed = matches!(self.peek_to(1), Some('+' | '-'));
+            if is_digit(self.peek_to(if signed { 2 } else { 1 })) {
+                self.advance();
+                if signed {
+                    self.advance();
+                }
+                self.decimal_digits();
+                exponent = true;
+            }
+        }
 
-        Ok(())
+        let lexeme = self.get_lexeme(start, self.current);
+        let valid = lexeme
+            .split(['.', 'e', 'E', '+', '-'])
+            .filter(|digits| !digits.is_empty())
+            .all(|digits| is_valid_digits(digits, 10));
+        let digits = lexeme.replace('_', "");
+
+        let token = match (fraction || exponent, valid) {
+            (false, true) => match digits.parse::<i64>() {
+                Ok(value) => Some(Token::new_integer(self.line, lexeme.to_string(), value)),
+                Err(_) => digits
+                    .parse::<f64>()
+                    .ok()
+                    .map(|value| Token::new_number(self.line, lexeme.to_string(), value)),
+            },
+            (true, true) => digits
+                .parse::<f64>()
+                .ok()
+                .map(|value| Token::new_number(self.line, lexeme.to_string(), value)),
+            (_, false) => None,
+        };
+
+        match token {
+            Some(token) => self.add_token(token),
+            None => self.error(LexError::InvalidNumber(
+                if fraction || exponent {
+                    "number"
+                } else {
+                    "integer"
+                },
+                lexeme.to_string(),
+                self.lexeme_span(),
+            )),
+        }
     }
 
-    fn peek(&mut self) -> Option<char> {
-        self.iter.peek().copied()
+    fn decimal_digits(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|chr| chr.is_ascii_digit() || chr == '_')
+        {
+            self.advance();
+        }
     }
 
-    fn peek_to(&mut self, n: usize) -> Option<char> {
-        self.iter.clone().nth(n)
+    fn radix_number(&mut self, start: usize) {
+        let (radix, name) = match self.advance() {
+            Some('x' | 'X') => (16, "hexadecimal"),
+            _ => (2, "binary"),
+        };
+
+        // take everything that could belong to the literal so that
+        // 0b102 is reported as a bad literal rather than two numbers
+        while self
+            .peek()
+            .is_some_and(|chr| chr.is_ascii_alphanumeric() || chr == '_')
+        {
+            self.advance();
+        }
+
+        let lexeme = self.get_lexeme(start, self.current);
+        let digits = &lexeme[2..];
+        let value = if is_valid_digits(digits, radix) {
+            i64::from_str_radix(&digits.replace('_', ""), radix).ok()
+        } else {
+            None
+        };
+
+        match value {
+            Some(value) => {
+                self.add_token(Token::new_integer(self.line, lexeme.to_string(), value));
+            }
+            None => self.error(LexError::InvalidNumber(
+                name,
+                lexeme.to_string(),
+                self.lexeme_span(),
+            )),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(self.current)
     }
 
-    fn pos(&self) -> usize {
-        self.pos.unwrap()
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.text[offset..].chars().next()
     }
 
-    fn string(&mut self) -> bool {
-        let start = self.pos();
-        while self.peek() != Some('"') && self.peek().is_some() {
-            if self.peek() == Some('\n') {
-                self.line += 1;
+    fn peek_to(&self, n: usize) -> Option<char> {
+        self.text[self.current..].chars().nth(n)
+    }
+
+    // lexes the rest of a string, up to the closing quote or the start of an
+    // interpolated expression, which ends the string part with a "${"
+    fn string(&mut self) {
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                None => {
+                    self.error(LexError::UnterminatedString(self.lexeme_span()));
+                    return;
+                }
+                Some('"') => break,
+                Some('$') if self.peek() == Some('{') => {
+                    self.advance();
+                    self.interpolations.push(0);
+                    self.add_token(Token::new_interpolation(self.line, value));
+                    return;
+                }
+                Some('\\') => {
+                    // the backslash is a single byte and column
+                    let start = (self.current - 1, (self.line, self.column - 1));
+                    if let Some(chr) = self.escape(start) {
+                        value.push(chr);
+                    }
+                }
+                Some(chr) => value.push(chr),
             }
-            self.advance();
         }
 
-        if self.peek().is_none() {
-            self.errors
-                .report_lex_error(self.line, "Unterminated string.");
-            return false;
+        self.add_token(Token::new_string(self.line, value));
+    }
+
+    // block comments nest, so /* a /* b */ c */ is a single comment
+    fn block_comment(&mut self) {
+        // consume the opening *
+        self.advance();
+
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance() {
+                Some('/') if self.match_char('*') => depth += 1,
+                Some('*') if self.match_char('/') => depth -= 1,
+                Some(_) => {}
+                None => {
+                    self.error(LexError::UnterminatedBlockComment(self.lexeme_span()));
+                    return;
+                }
+            }
         }
 
-        // consume the closing "
+        self.add_trivia(TokenType::Comment);
+    }
+
+    // \n, \t, \r, \0, \\, \", \$ and \u{<hex>}
+    fn escape(&mut self, start: (usize, (usize, usize))) -> Option<char> {
+        let chr = match self.peek() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('$') => '$',
+            Some('u') => {
+                self.advance();
+                return self.unicode_escape(start);
+            }
+            // let the string report that it is unterminated
+            None => return None,
+            Some(chr) => {
+                self.advance();
+                self.error(LexError::InvalidEscape(
+                    chr,
+                    self.span_from(start.0, start.1),
+                ));
+                return None;
+            }
+        };
+
         self.advance();
+        Some(chr)
+    }
+
+    fn unicode_escape(&mut self, start: (usize, (usize, usize))) -> Option<char> {
+        let mut digits = String::new();
+        if self.match_char('{') {
+            while let Some(chr) = self.peek().filter(char::is_ascii_hexdigit) {
+                digits.push(chr);
+                self.advance();
+            }
+
+            if self.match_char('}') && (1..=6).contains(&digits.len()) {
+                let chr = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32);
+                if chr.is_some() {
+                    return chr;
+                }
+            }
+        }
+
+        self.error(LexError::InvalidUnicodeEscape(
+            digits,
+            self.span_from(start.0, start.1),
+        ));
+        None
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.finished {
+            self.scan_token();
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::token_value::TokenValue;
+    use rstest::*;
+
+    fn tokenize(source: &str, trivia: bool) -> Vec<Token> {
+        let lexer = Lexer::new(source).with_trivia(trivia);
+        lexer.collect::<Result<_, _>>().unwrap()
+    }
+
+    fn string_values(tokens: &[Token]) -> Vec<(TokenType, String)> {
+        tokens
+            .iter()
+            .filter_map(|token| match &token.value {
+                TokenValue::String(value) => Some((token.token_type, value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(r#""a\nb""#, "a\nb")]
+    #[case(r#""\t\\\"""#, "\t\\\"")]
+    #[case(r#""\u{48}\u{1F600}""#, "H\u{1F600}")]
+    #[case(r#""cost \${x} $5""#, "cost ${x} $5")]
+    fn test_string_escapes(#[case] source: &str, #[case] expected: &str) {
+        let tokens = tokenize(source, false);
+        assert_eq!(
+            string_values(&tokens),
+            vec![(TokenType::String, expected.to_string())]
+        );
+    }
+
+    #[rstest]
+    #[case(r#""\q""#, LexError::InvalidEscape('q', Span::new(1, 3, (1, 2), (1, 4))))]
+    #[case(r#""\u{}""#, LexError::InvalidUnicodeEscape("".to_string(), Span::new(1, 5, (1, 2), (1, 6))))]
+    #[case(r#""\u{110000}""#, LexError::InvalidUnicodeEscape("110000".to_string(), Span::new(1, 11, (1, 2), (1, 12))))]
+    #[case(r#""\u41""#, LexError::InvalidUnicodeEscape("".to_string(), Span::new(1, 3, (1, 2), (1, 4))))]
+    #[case(r#""${a"#, LexError::UnterminatedInterpolation(Span::new(4, 4, (1, 5), (1, 5))))]
+    #[case("\"a\nb", LexError::UnterminatedString(Span::new(0, 4, (1, 1), (2, 2))))]
+    fn test_invalid_strings(#[case] source: &str, #[case] expected: LexError) {
+        let errors: Vec<_> = Lexer::new(source).filter_map(Result::err).collect();
+        assert_eq!(errors, vec![expected]);
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let tokens = tokenize(r#""a ${b + "${c}"} { ${ {} } d""#, false);
+        let types: Vec<_> = tokens.iter().map(|token| token.token_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Interpolation,
+                TokenType::Identifier,
+                TokenType::Plus,
+                TokenType::Interpolation,
+                TokenType::Identifier,
+                TokenType::String,
+                TokenType::Interpolation,
+                TokenType::LeftBrace,
+                TokenType::RightBrace,
+                TokenType::String,
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(
+            string_values(&tokens),
+            vec![
+                (TokenType::Interpolation, "a ".to_string()),
+                (TokenType::Interpolation, "".to_string()),
+                (TokenType::String, "".to_string()),
+                (TokenType::Interpolation, " { ".to_string()),
+                (TokenType::String, " d".to_string()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("a /* b */ c")]
+    #[case("a /* b /* c */ d */ c")]
+    #[case("a /**/ c")]
+    #[case("a /*/ b */ c")]
+    fn test_block_comments(#[case] source: &str) {
+        let tokens = tokenize(source, false);
+        let lexemes: Vec<_> = tokens.iter().map(|token| token.lexeme.clone()).collect();
+        assert_eq!(
+            lexemes,
+            vec![
+                Some("a".to_string()),
+                Some("c".to_string()),
+                Some("".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_comment_lines() {
+        let tokens = tokenize("/* a\n/* b\n*/\n*/ c", false);
+        assert_eq!(tokens[0].line, 4);
+        assert_eq!(tokens[0].span.column, 4);
+
+        let tokens = tokenize("/* a */", true);
+        assert_eq!(tokens[0].token_type, TokenType::Comment);
+        assert_eq!(tokens[0].lexeme, Some("/* a */".to_string()));
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let errors: Vec<_> = Lexer::new("a /* b /* c */\n\n")
+            .filter_map(Result::err)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![LexError::UnterminatedBlockComment(Span::new(
+                2,
+                16,
+                (1, 3),
+                (3, 1)
+            ))]
+        );
+    }
+
+    #[rstest]
+    #[case("123", Some(TokenValue::Integer(123)))]
+    #[case("123.45", Some(TokenValue::Number(123.45)))]
+    #[case(".5", Some(TokenValue::Number(0.5)))]
+    #[case("1_000_000", Some(TokenValue::Integer(1_000_000)))]
+    #[case("1e-9", Some(TokenValue::Number(1e-9)))]
+    #[case("2.5E+3", Some(TokenValue::Number(2.5e3)))]
+    #[case("1_0e1_0", Some(TokenValue::Number(10e10)))]
+    #[case("0xFF", Some(TokenValue::Integer(255)))]
+    #[case("0XdEaD_bEeF", Some(TokenValue::Integer(3_735_928_559)))]
+    #[case("0b1010", Some(TokenValue::Integer(10)))]
+    #[case("0b_1", None)]
+    #[case("0x", None)]
+    #[case("0b102", None)]
+    #[case("0xFG", None)]
+    #[case("1__0", None)]
+    #[case("1_", None)]
+    #[case("1_.5", None)]
+    #[case("1.5_", None)]
+    #[case("0x8000_0000_0000_0000", None)]
+    #[case("9_223_372_036_854_775_807", Some(TokenValue::Integer(i64::MAX)))]
+    #[case(
+        "9_223_372_036_854_775_808",
+        Some(TokenValue::Number(9_223_372_036_854_775_808.0))
+    )]
+    #[case("99999999999999999999", Some(TokenValue::Number(1e20)))]
+    fn test_numbers(#[case] source: &str, #[case] expected: Option<TokenValue>) {
+        let result: Result<Vec<_>, _> = Lexer::new(source).collect();
+
+        match expected {
+            Some(expected) => {
+                let tokens = result.unwrap();
+                assert_eq!(tokens.len(), 2);
+                assert_eq!(tokens[0].value, expected);
+                assert_eq!(tokens[0].lexeme, Some(source.to_string()));
+            }
+            None => assert!(result.is_err()),
+        }
+    }
+
+    #[test]
+    fn test_number_boundaries() {
+        let types = |source| -> Vec<TokenType> {
+            tokenize(source, false)
+                .iter()
+                .map(|token| token.token_type)
+                .collect()
+        };
+
+        assert_eq!(
+            types("123.a"),
+            vec![
+                TokenType::Number,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::Eof
+            ]
+        );
+        assert_eq!(
+            types("1e"),
+            vec![TokenType::Number, TokenType::Identifier, TokenType::Eof]
+        );
+        assert_eq!(
+            types("1-2"),
+            vec![
+                TokenType::Number,
+                TokenType::Minus,
+                TokenType::Number,
+                TokenType::Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexing_is_lazy() {
+        let mut lexer = Lexer::new("a @ b");
+        assert_eq!(lexer.next().unwrap().unwrap().lexeme, Some("a".to_string()));
+        assert_eq!(lexer.pending.len(), 0);
+        assert_eq!(
+            lexer.next(),
+            Some(Err(LexError::UnexpectedCharacter(
+                '@',
+                Span::new(2, 3, (1, 3), (1, 4))
+            )))
+        );
+        assert_eq!(lexer.next().unwrap().unwrap().lexeme, Some("b".to_string()));
+        assert_eq!(lexer.next().unwrap().unwrap().token_type, TokenType::Eof);
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let tokens = tokenize("var a =\n  \"b\nc\";", false);
+
+        let spans: Vec<_> = tokens.iter().map(|token| token.span).collect();
+        assert_eq!(spans[0], Span::new(0, 3, (1, 1), (1, 4)));
+        assert_eq!(spans[1], Span::new(4, 5, (1, 5), (1, 6)));
+        assert_eq!(spans[3], Span::new(10, 15, (2, 3), (3, 3)));
+        assert_eq!(spans[4], Span::new(15, 16, (3, 3), (3, 4)));
+        assert_eq!(tokens[3].lexeme, Some("\"b\nc\"".to_string()));
+        assert_eq!(tokens[3].line, 3);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let tokens = tokenize("var größe = \"ü\"; 名前_2 _x", false);
+
+        assert_eq!(tokens[1].value, TokenValue::Identifier("größe".to_string()));
+        assert_eq!(tokens[1].span, Span::new(4, 11, (1, 5), (1, 10)));
+        assert_eq!(tokens[3].span, Span::new(14, 18, (1, 13), (1, 16)));
+        assert_eq!(
+            tokens[5].value,
+            TokenValue::Identifier("名前_2".to_string())
+        );
+        assert_eq!(tokens[5].span.column, 18);
+        assert_eq!(tokens[6].value, TokenValue::Identifier("_x".to_string()));
+    }
 
-        // trim the surrounding quotes
-        let lexeme = self.get_lexeme(start + 1, self.pos() - 1);
-        self.add_token(Token::new_string(self.line, lexeme));
-        true
+    #[test]
+    fn test_trivia() {
+        let types: Vec<_> = tokenize("a // note\nb", true)
+            .iter()
+            .map(|token| token.token_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Identifier,
+                TokenType::Whitespace,
+                TokenType::Comment,
+                TokenType::Whitespace,
+                TokenType::Identifier,
+                TokenType::Eof
+            ]
+        );
+
+        assert_eq!(tokenize("a // note\nb", false).len(), 3);
     }
 }
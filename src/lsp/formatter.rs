@@ -0,0 +1,177 @@
+use crate::{
+    lexer::Lexer,
+    token::{token_type::TokenType, Token},
+};
+
+const INDENT: &str = "    ";
+
+// Formats a document from its tokens rather than its syntax tree, so comments
+// are kept and a document that doesn't parse can still be formatted. Blocks
+// are indented, every statement starts a line, at most one blank line is kept
+// between statements and lines broken inside a statement stay broken.
+// Returns None when the document has lexical errors.
+pub fn format(source: &str) -> Option<String> {
+    let tokens = Lexer::new(source)
+        .with_trivia(true)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    let mut output = String::new();
+    let mut indent = 0usize;
+    let mut parens = 0usize;
+    let mut newlines = 0;
+    // the last two tokens written, most recent first
+    let mut previous: Option<&Token> = None;
+    let mut before_previous: Option<&Token> = None;
+
+    for token in &tokens {
+        match token.token_type {
+            TokenType::Whitespace => {
+                newlines += text(source, token).matches('\n').count();
+                continue;
+            }
+            TokenType::Eof => break,
+            TokenType::RightBrace => indent = indent.saturating_sub(1),
+            _ => {}
+        }
+
+        if let Some(previous) = previous {
+            let trailing_comment = token.token_type == TokenType::Comment && newlines == 0;
+            let line_break = match ends_line(previous, token, parens, source) {
+                _ if trailing_comment => None,
+                Some(true) => Some(indent),
+                // a statement continued on the next line
+                None if newlines > 0 => Some(indent + 1),
+                _ => None,
+            };
+
+            if let Some(depth) = line_break {
+                output.push('\n');
+                if newlines > 1 && depth == indent {
+                    output.push('\n');
+                }
+                output.push_str(&INDENT.repeat(depth));
+            } else if spaced(before_previous, previous, token, source) {
+                output.push(' ');
+            }
+        }
+
+        output.push_str(text(source, token));
+        match token.token_type {
+            TokenType::LeftBrace => indent += 1,
+            TokenType::LeftParen => parens += 1,
+            TokenType::RightParen => parens = parens.saturating_sub(1),
+            _ => {}
+        }
+
+        newlines = 0;
+        before_previous = previous;
+        previous = Some(token);
+    }
+
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    Some(output)
+}
+
+// whether the next token has to start a new line, None leaves it to the source
+fn ends_line(previous: &Token, next: &Token, parens: usize, source: &str) -> Option<bool> {
+    match (previous.token_type, next.token_type) {
+        (_, TokenType::RightBrace) => Some(true),
+        (_, TokenType::Semicolon) => Some(false),
+        (TokenType::RightBrace, TokenType::Else) => Some(false),
+        (TokenType::LeftBrace | TokenType::RightBrace, _) => Some(true),
+        // the semicolons between the clauses of a for loop are inside parentheses
+        (TokenType::Semicolon, _) if parens == 0 => Some(true),
+        (TokenType::Comment, _) if text(source, previous).starts_with("//") => Some(true),
+        _ => None,
+    }
+}
+
+// whether a space separates two tokens on the same line
+fn spaced(before_previous: Option<&Token>, previous: &Token, next: &Token, source: &str) -> bool {
+    match (previous.token_type, next.token_type) {
+        (_, TokenType::RightParen | TokenType::Comma | TokenType::Semicolon | TokenType::Dot) => {
+            false
+        }
+        (
+            TokenType::LeftParen
+            | TokenType::Dot
+            | TokenType::DotDotDot
+            | TokenType::Bang
+            | TokenType::Tilde,
+            _,
+        ) => false,
+        // a named argument, as opposed to the ':' of `a ? b : c`
+        (TokenType::Identifier, TokenType::Colon) => !before_previous.is_some_and(|token| {
+            matches!(token.token_type, TokenType::LeftParen | TokenType::Comma)
+        }),
+        // a call, as opposed to `if (`
+        (TokenType::Identifier | TokenType::RightParen, TokenType::LeftParen) => false,
+        (TokenType::Minus, _) => before_previous.is_some_and(ends_operand),
+        // inside "a ${b} c" the expression hugs the braces
+        (TokenType::Interpolation, _) => false,
+        (_, TokenType::String | TokenType::Interpolation) => !text(source, next).starts_with('}'),
+        _ => true,
+    }
+}
+
+// tokens that can end an operand, after which a '-' subtracts rather than negates
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token.token_type,
+        TokenType::Identifier
+            | TokenType::Number
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::This
+            | TokenType::RightParen
+    )
+}
+
+fn text<'a>(source: &'a str, token: &Token) -> &'a str {
+    &source[token.span.start..token.span.end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("var  a=1 ;", "var a = 1;\n")]
+    #[case("print -a - -1;print !a;", "print -a - -1;\nprint !a;\n")]
+    #[case("fun f(a,b){return a+b;}", "fun f(a, b) {\n    return a + b;\n}\n")]
+    #[case(
+        "if (a) { print 1; }\nelse { print 2; }",
+        "if (a) {\n    print 1;\n} else {\n    print 2;\n}\n"
+    )]
+    #[case(
+        "for (var i=0;i<3;i=i+1) print i;",
+        "for (var i = 0; i < 3; i = i + 1) print i;\n"
+    )]
+    #[case(
+        "var a = 1; // one\n\n\n// two\nvar b = m.f( a );",
+        "var a = 1; // one\n\n// two\nvar b = m.f(a);\n"
+    )]
+    #[case("print \"a ${ b + 1 } c\";", "print \"a ${b + 1} c\";\n")]
+    #[case("print f(/* none */);", "print f(/* none */);\n")]
+    #[case("f(a?b:c, x : 1);", "f(a ? b : c, x: 1);\n")]
+    #[case(
+        "print a +\nb;\n{\n/* note */\n}",
+        "print a +\n    b;\n{\n    /* note */\n}\n"
+    )]
+    fn test_format(#[case] source: &str, #[case] expected: &str) {
+        let formatted = format(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_lexical_errors() {
+        assert_eq!(format("var a = \"open"), None);
+    }
+}
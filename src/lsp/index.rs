@@ -0,0 +1,258 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    parser::{
+        expression::Expression,
+        statement::{Parameter, Statement},
+    },
+    token::{span::Span, Token},
+    visitor::{expression_visitor::ExpressionVisitor, statement_visitor::StatementVisitor},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Constant,
+    Function,
+    Module,
+    Parameter,
+    Variable,
+}
+
+// a declared name, `detail` is how it reads where it was declared
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: Span,
+    pub detail: String,
+    // the function it was declared in
+    pub parent: Option<usize>,
+}
+
+// Every declaration in a document and every place a declared name is used,
+// found by following the same scopes as the resolver. The name in a
+// declaration counts as one of its references.
+#[derive(Debug, Default)]
+pub struct Index {
+    pub symbols: Vec<Symbol>,
+    pub references: Vec<(Span, usize)>,
+}
+
+impl Index {
+    pub fn new(statements: &[Statement]) -> Self {
+        let mut indexer = Indexer {
+            index: Index::default(),
+            scopes: vec![HashMap::new()],
+            functions: Vec::new(),
+            unresolved: Vec::new(),
+        };
+        indexer.index_all(statements);
+
+        // names that weren't declared where they were used can only be globals
+        // declared further down
+        for name in std::mem::take(&mut indexer.unresolved) {
+            if let Some(&symbol) = indexer.scopes[0].get(&name.value.to_string()) {
+                indexer.index.references.push((name.span, symbol));
+            }
+        }
+
+        indexer.index.references.sort_by_key(|(span, _)| span.start);
+        indexer.index
+    }
+
+    // the symbol named at a 1-based line and character column
+    pub fn symbol_at(&self, line: usize, column: usize) -> Option<usize> {
+        self.references
+            .iter()
+            .find(|(span, _)| {
+                span.line == line && span.column <= column && column <= span.end_column
+            })
+            .map(|(_, symbol)| *symbol)
+    }
+
+    pub fn references_to(&self, symbol: usize) -> impl Iterator<Item = &Span> {
+        self.references
+            .iter()
+            .filter(move |(_, target)| *target == symbol)
+            .map(|(span, _)| span)
+    }
+}
+
+struct Indexer {
+    index: Index,
+    scopes: Vec<HashMap<String, usize>>,
+    // the functions being indexed, innermost last
+    functions: Vec<usize>,
+    unresolved: Vec<Token>,
+}
+
+impl Indexer {
+    fn declare(&mut self, name: &Token, kind: SymbolKind, detail: String) -> usize {
+        let symbol = self.index.symbols.len();
+        self.index.symbols.push(Symbol {
+            name: name.value.to_string(),
+            kind,
+            span: name.span,
+            detail,
+            parent: self.functions.last().copied(),
+        });
+        self.index.references.push((name.span, symbol));
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.value.to_string(), symbol);
+        symbol
+    }
+
+    fn index_all(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let _ = statement.accept(self);
+        }
+    }
+
+    fn reference(&mut self, name: &Token) {
+        let key = name.value.to_string();
+        match self.scopes.iter().rev().find_map(|scope| scope.get(&key)) {
+            Some(&symbol) => self.index.references.push((name.span, symbol)),
+            None => self.unresolved.push(name.clone()),
+        }
+    }
+}
+
+impl StatementVisitor<(), ()> for Indexer {
+    fn visit_expression(&mut self, expression: &Expression) -> Result<(), ()> {
+        expression.accept(self)
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Statement]) -> Result<(), ()> {
+        self.scopes.push(HashMap::new());
+        self.index_all(statements);
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn visit_constant_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Expression,
+    ) -> Result<(), ()> {
+        self.visit_expression(initializer)?;
+        self.declare(name, SymbolKind::Constant, format!("const {}", name.value));
+        Ok(())
+    }
+
+    fn visit_function_statement(
+        &mut self,
+        name: &Token,
+        params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<(), ()> {
+        let names = params
+            .iter()
+            .map(|param| {
+                let rest = if param.rest { "..." } else { "" };
+                format!("{}{}", rest, param.name.value)
+            })
+            .collect::<Vec<_>>();
+        let detail = format!("fun {}({})", name.value, names.join(", "));
+        let function = self.declare(name, SymbolKind::Function, detail);
+
+        self.functions.push(function);
+        self.scopes.push(HashMap::new());
+        for param in params.iter() {
+            if let Some(default) = &param.default {
+                default.accept(self)?;
+            }
+            let detail = format!("parameter {} of {}", param.name.value, name.value);
+            self.declare(&param.name, SymbolKind::Parameter, detail);
+        }
+        self.index_all(body);
+        self.scopes.pop();
+        self.functions.pop();
+
+        Ok(())
+    }
+
+    fn visit_import_statement(
+        &mut self,
+        path: &Token,
+        alias: &Option<Token>,
+        names: &[Token],
+    ) -> Result<(), ()> {
+        if let Some(alias) = alias {
+            let detail = format!("import \"{}\" as {}", path.value, alias.value);
+            self.declare(alias, SymbolKind::Module, detail);
+        }
+        for name in names {
+            let detail = format!("from \"{}\" import {}", path.value, name.value);
+            self.declare(name, SymbolKind::Variable, detail);
+        }
+        Ok(())
+    }
+
+    fn visit_variable_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expression>,
+    ) -> Result<(), ()> {
+        if let Some(initializer) = initializer {
+            self.visit_expression(initializer)?;
+        }
+        self.declare(name, SymbolKind::Variable, format!("var {}", name.value));
+        Ok(())
+    }
+}
+
+impl ExpressionVisitor<(), ()> for Indexer {
+    fn visit_assignment(&mut self, name: &Token, expression: &Expression) -> Result<(), ()> {
+        expression.accept(self)?;
+        self.reference(name);
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, name: &Token) -> Result<(), ()> {
+        self.reference(name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser};
+
+    fn index(source: &str) -> Index {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+        Index::new(&statements)
+    }
+
+    #[test]
+    fn test_references() {
+        let index = index("var a = 1;\nfun f(a) { return a + b; }\nvar b = a;\nb = f(b);");
+        let names: Vec<_> = index
+            .symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.parent))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("a", SymbolKind::Variable, None),
+                ("f", SymbolKind::Function, None),
+                ("a", SymbolKind::Parameter, Some(1)),
+                ("b", SymbolKind::Variable, None),
+            ]
+        );
+
+        // the parameter shadows the global inside f
+        assert_eq!(index.symbol_at(2, 19), Some(2));
+        assert_eq!(index.symbol_at(3, 9), Some(0));
+        // b is used in f before it is declared
+        let lines: Vec<_> = index.references_to(3).map(|span| span.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 4]);
+        assert_eq!(index.symbol_at(1, 7), None);
+    }
+}
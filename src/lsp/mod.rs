@@ -0,0 +1,473 @@
+pub mod formatter;
+pub mod index;
+pub mod transport;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use index::{Index, SymbolKind};
+use serde_json::{json, Value};
+
+use crate::{
+    error::LoxError, error_bag::ErrorBag, interpreter::Interpreter, lexer::Lexer, parser::Parser,
+    resolver::Resolver, token::span::Span,
+};
+
+// JSON-RPC error codes from the LSP specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// An open document and what was found in it the last time it changed
+struct Document {
+    text: String,
+    index: Index,
+}
+
+// A language server for Lox, speaking LSP over stdio. Documents are synced in
+// full on every change and checked by the same lexer, parser and resolver
+// that `interpret` uses.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+// serves requests until the client sends exit or closes the stream,
+// returning whether it shut down cleanly first. A malformed message gets a
+// parse error back and the server carries on.
+pub fn run(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<bool> {
+    let mut server = Server::default();
+    loop {
+        let message = match transport::read_message(input) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(failure) if failure.kind() == io::ErrorKind::InvalidData => {
+                let reply = error(&Value::Null, PARSE_ERROR, &failure.to_string());
+                transport::write_message(output, &reply)?;
+                continue;
+            }
+            Err(failure) => return Err(failure),
+        };
+
+        if message["method"] == "exit" {
+            break;
+        }
+
+        for reply in server.handle(&message) {
+            transport::write_message(output, &reply)?;
+        }
+    }
+
+    Ok(server.shutdown)
+}
+
+impl Server {
+    // handles one message from the client, returning the response to a
+    // request and any notifications to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let Some(id) = message.get("id") else {
+            return self.notification(method, params);
+        };
+
+        if self.shutdown {
+            return vec![error(id, INVALID_REQUEST, "The server is shutting down.")];
+        }
+
+        let result = match method {
+            "initialize" => initialize(),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/formatting" => self.formatting(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/references" => self.references(params),
+            _ => {
+                let message = format!("Unknown method: {}", method);
+                return vec![error(id, METHOD_NOT_FOUND, &message)];
+            }
+        };
+
+        vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })]
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str(),
+            // the server asks for full syncs, so the last change is the whole document
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()
+                .and_then(|changes| changes.last())
+                .and_then(|change| change["text"].as_str()),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, Vec::new())];
+            }
+            _ => None,
+        };
+
+        match text {
+            Some(text) => vec![self.update(uri, text.to_string())],
+            None => Vec::new(),
+        }
+    }
+
+    // checks a document that was opened or changed, returning its diagnostics
+    fn update(&mut self, uri: &str, text: String) -> Value {
+        let mut errors = ErrorBag::new(Box::new(io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(&text)).parse();
+
+        let mut index = None;
+        if let Ok(statements) = &statements {
+            let natives = Interpreter::new().globals.borrow().names();
+            Resolver::new(&mut errors)
+                .with_natives(natives)
+                .resolve(statements);
+            index = Some(Index::new(statements));
+        }
+
+        let diagnostics = errors
+            .errors()
+            .iter()
+            .map(|error| diagnostic(&text, error))
+            .collect();
+
+        // while the document doesn't parse, navigation uses the last version that did
+        let index = match (index, self.documents.remove(uri)) {
+            (Some(index), _) => index,
+            (None, Some(document)) => document.index,
+            (None, None) => Index::default(),
+        };
+        self.documents
+            .insert(uri.to_string(), Document { text, index });
+
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let Some((document, uri, symbol)) = self.symbol_at(params) else {
+            return Value::Null;
+        };
+
+        let span = document.index.symbols[symbol].span;
+        json!({ "uri": uri, "range": range(&document.text, span) })
+    }
+
+    fn document_symbols(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(document) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+
+        symbol_tree(document, None)
+    }
+
+    fn formatting(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(document) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+
+        match formatter::format(&document.text) {
+            Some(formatted) if formatted != document.text => {
+                let whole = Span {
+                    end: document.text.len(),
+                    ..Span::default()
+                };
+                json!([{ "range": range(&document.text, whole), "newText": formatted }])
+            }
+            Some(_) => json!([]),
+            None => Value::Null,
+        }
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((document, _, symbol)) = self.symbol_at(params) else {
+            return Value::Null;
+        };
+
+        let symbol = &document.index.symbols[symbol];
+        let contents = format!(
+            "```lox\n{}\n```\nDeclared on line {}, column {}.",
+            symbol.detail, symbol.span.line, symbol.span.column
+        );
+        json!({ "contents": { "kind": "markdown", "value": contents } })
+    }
+
+    fn references(&self, params: &Value) -> Value {
+        let Some((document, uri, symbol)) = self.symbol_at(params) else {
+            return Value::Null;
+        };
+
+        let declaration = document.index.symbols[symbol].span;
+        let include_declaration = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(true);
+        document
+            .index
+            .references_to(symbol)
+            .filter(|span| include_declaration || **span != declaration)
+            .map(|span| json!({ "uri": uri, "range": range(&document.text, *span) }))
+            .collect()
+    }
+
+    // the symbol named at the position in a text document request
+    fn symbol_at<'a>(&'a self, params: &'a Value) -> Option<(&'a Document, &'a str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?;
+
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        let column = column(&document.text, line, character);
+
+        let symbol = document.index.symbol_at(line + 1, column)?;
+        Some((document, uri, symbol))
+    }
+}
+
+fn initialize() -> Value {
+    json!({
+        "capabilities": {
+            // full document sync
+            "textDocumentSync": 1,
+            "definitionProvider": true,
+            "referencesProvider": true,
+            "hoverProvider": true,
+            "documentSymbolProvider": true,
+            "documentFormattingProvider": true,
+        },
+        "serverInfo": {
+            "name": "rust-lox",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+fn error(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn diagnostic(text: &str, error: &LoxError) -> Value {
+    json!({
+        "range": range(text, error.span().unwrap_or_default()),
+        // error
+        "severity": 1,
+        "code": error.code(),
+        "source": "rust-lox",
+        "message": error.message(),
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+// the fun, var and const declarations inside a function, or at the top level
+// when there is no function
+fn symbol_tree(document: &Document, parent: Option<usize>) -> Value {
+    let symbols = &document.index.symbols;
+    symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| symbol.parent == parent)
+        .filter_map(|(id, symbol)| {
+            let kind = match symbol.kind {
+                SymbolKind::Function => 12,
+                SymbolKind::Variable => 13,
+                SymbolKind::Constant => 14,
+                SymbolKind::Module | SymbolKind::Parameter => return None,
+            };
+
+            let range = range(&document.text, symbol.span);
+            let mut node = json!({
+                "name": symbol.name,
+                "detail": symbol.detail,
+                "kind": kind,
+                "range": range,
+                "selectionRange": range,
+            });
+            if symbol.kind == SymbolKind::Function {
+                node["children"] = symbol_tree(document, Some(id));
+            }
+            Some(node)
+        })
+        .collect()
+}
+
+// LSP positions are 0-based and count UTF-16 code units, spans count
+// characters from 1 or bytes from 0
+fn range(text: &str, span: Span) -> Value {
+    let (start, end) = if span.line == 0 {
+        (position(text, span.start), position(text, span.end))
+    } else {
+        (
+            (span.line - 1, character(text, span.line - 1, span.column)),
+            (
+                span.end_line - 1,
+                character(text, span.end_line - 1, span.end_column),
+            ),
+        )
+    };
+
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+// the 0-based line and UTF-16 character of a byte offset
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (line, before[line_start..].encode_utf16().count())
+}
+
+// the UTF-16 character of a 1-based character column on a 0-based line
+fn character(text: &str, line: usize, column: usize) -> usize {
+    let line = text.split('\n').nth(line).unwrap_or_default();
+    line.chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum()
+}
+
+// the 1-based character column of a UTF-16 character on a 0-based line
+fn column(text: &str, line: usize, character: usize) -> usize {
+    let line = text.split('\n').nth(line).unwrap_or_default();
+    let mut units = 0;
+    let mut column = 1;
+    for chr in line.chars() {
+        if units >= character {
+            break;
+        }
+        units += chr.len_utf16();
+        column += 1;
+    }
+    column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///test.lox";
+
+    fn open(server: &mut Server, text: &str) -> Value {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": URI, "languageId": "lox", "version": 1, "text": text } },
+        });
+        server.handle(&message).remove(0)
+    }
+
+    fn request(server: &mut Server, method: &str, params: Value) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        server.handle(&message).remove(0)["result"].take()
+    }
+
+    fn at(line: usize, character: usize) -> Value {
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": line, "character": character },
+            "context": { "includeDeclaration": true },
+        })
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut server = Server::default();
+        let published = open(&mut server, "var a = 1 @;\nconst b = 1;\nb = 2;");
+        let diagnostics = &published["params"]["diagnostics"];
+        assert_eq!(diagnostics.as_array().unwrap().len(), 2);
+        assert_eq!(diagnostics[0]["code"], "E0001");
+        assert_eq!(
+            diagnostics[0]["range"]["start"],
+            json!({ "line": 0, "character": 10 })
+        );
+        assert_eq!(diagnostics[1]["message"], "Can't assign to constant 'b'.");
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut server = Server::default();
+        open(
+            &mut server,
+            "var é = 1;\nfun f(x) { return x + é; }\nprint f(é);",
+        );
+
+        let definition = request(&mut server, "textDocument/definition", at(2, 8));
+        assert_eq!(
+            definition["range"]["start"],
+            json!({ "line": 0, "character": 4 })
+        );
+
+        let references = request(&mut server, "textDocument/references", at(0, 4));
+        assert_eq!(references.as_array().unwrap().len(), 3);
+
+        let hover = request(&mut server, "textDocument/hover", at(1, 18));
+        let hover = hover["contents"]["value"].as_str().unwrap();
+        assert!(hover.contains("parameter x of f"));
+
+        let symbols = request(&mut server, "textDocument/documentSymbol", at(0, 0));
+        let names: Vec<_> = symbols
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|symbol| symbol["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["é", "f"]);
+    }
+
+    #[test]
+    fn test_formatting() {
+        let mut server = Server::default();
+        open(&mut server, "var a=1;\nprint a;");
+        let edits = request(&mut server, "textDocument/formatting", at(0, 0));
+        assert_eq!(edits[0]["newText"], "var a = 1;\nprint a;\n");
+        assert_eq!(
+            edits[0]["range"]["end"],
+            json!({ "line": 1, "character": 8 })
+        );
+    }
+
+    #[test]
+    fn test_malformed_message() {
+        let mut input = Vec::new();
+        write!(input, "Content-Length: 10\r\n\r\n{{not json}}").unwrap();
+        transport::write_message(
+            &mut input,
+            &json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" }),
+        )
+        .unwrap();
+
+        let mut output = Vec::new();
+        assert!(run(&mut input.as_slice(), &mut output).unwrap());
+
+        let mut output = output.as_slice();
+        let reply = transport::read_message(&mut output).unwrap().unwrap();
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+        let reply = transport::read_message(&mut output).unwrap().unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"], Value::Null);
+    }
+}
@@ -1,129 +1,362 @@
 use anyhow::Result;
+use coverage::Coverage;
+use debugger::Debugger;
+use error::{LoxError, Phase, UsageError};
 use error_bag::ErrorBag;
+use interpreter::{
+    capability::Capability,
+    hook::Hook,
+    limits::{Limits, STACK_SIZE},
+    runtime_error::RuntimeError,
+    Input,
+};
 use lexer::Lexer;
 use parser::statement::Statement;
 use parser::Parser;
+use profiler::Profiler;
+use resolver::Resolver;
+use std::cell::RefCell;
 use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
-use token::Token;
-use visitor::expression_printer::ExpressionPrinter;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+use tracer::Tracer;
+use visitor::{json_printer::JsonPrinter, statement_printer::StatementPrinter};
 
+mod coverage;
+mod debugger;
+mod error;
 mod error_bag;
 mod interpreter;
 mod lexer;
+mod lsp;
 mod parser;
+mod profiler;
+mod resolver;
+#[cfg(test)]
+mod test_output;
 mod token;
+mod tracer;
 mod visitor;
 
-#[derive(thiserror::Error, Debug, PartialEq)]
-pub enum InterpreterError {
-    #[error("Invalid command. Usage: {0} <{1}> <filename>")]
-    InvalidCommand(String, String),
-
-    #[error("Unknown command: {0}")]
-    UnknownCommand(String),
-
-    #[error("Lexical failure")]
-    LexicalFailure,
-
-    #[error("Parser failure: {0}")]
-    ParserFailure(#[from] crate::parser::parse_error::ParseError),
-
-    #[error("Interpreter failure: {0}")]
-    InterpreterFailure(#[from] crate::interpreter::interpreter_error::InterpreterError),
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
 
-    #[error("Failed to read file {0}")]
-    InvalidFile(String),
+#[derive(Debug, Default)]
+struct Options {
+    format: OutputFormat,
+    trivia: bool,
+    strict: bool,
+    trace: bool,
+    profile: bool,
+    // where to write the profile as folded stacks for flamegraph tools
+    folded: Option<String>,
+    // where to write which lines and branches ran, in lcov format
+    coverage: Option<String>,
+    limits: Limits,
+    // what natives may do, from the --allow-* options
+    capabilities: Vec<Capability>,
+    // reading the clock is allowed unless this is set, even by --allow-all
+    deny_time: bool,
+    // everything after the filename, for the script to read with args()
+    args: Vec<String>,
 }
 
 enum InterpreterCommand {
-    Tokenize(String),
-    Parse(String),
-    Interpret(String),
+    Tokenize(String, Options),
+    Parse(String, Options),
+    Interpret(String, Options),
+    Debug(String, Options),
+    Explain(String),
+    Lsp,
 }
 
+// the interpreter recurses on the native stack, so commands run on a thread
+// with a stack big enough for the default limits
 fn main() -> Result<()> {
-    let command = handle_args();
-    if command.is_err() {
-        let error = command.err().unwrap();
-        let message = format!("{}", error);
-        writeln!(io::stderr(), "{}", message)?;
-        return Err(anyhow::Error::msg(message));
+    let command = thread::Builder::new().stack_size(STACK_SIZE).spawn(run)?;
+    match command.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
     }
+}
 
+fn run() -> Result<()> {
     let mut errors = error_bag::ErrorBag::default();
-    let error = match command.ok().unwrap() {
-        InterpreterCommand::Tokenize(filename) => tokenize_file(&filename, &mut errors, true).err(),
-        InterpreterCommand::Parse(filename) => parse_file(&filename, &mut errors, true).err(),
-        InterpreterCommand::Interpret(filename) => interpret_file(&filename, &mut errors).err(),
+    let error = match handle_args() {
+        Err(error) => Some(error.into()),
+        Ok(InterpreterCommand::Tokenize(filename, options)) => {
+            tokenize_file(&filename, &mut errors, &options).err()
+        }
+        Ok(InterpreterCommand::Parse(filename, options)) => {
+            parse_file(&filename, &mut errors, &options, true).err()
+        }
+        Ok(InterpreterCommand::Interpret(filename, options)) => {
+            interpret_file(&filename, &mut errors, &options, None, None).err()
+        }
+        Ok(InterpreterCommand::Debug(filename, options)) => {
+            debug_file(&filename, &mut errors, &options).err()
+        }
+        Ok(InterpreterCommand::Explain(code)) => explain_code(&code).err(),
+        Ok(InterpreterCommand::Lsp) => {
+            // the exit code tells the client whether shutdown came before exit
+            let clean = lsp::run(&mut io::stdin().lock(), &mut io::stdout().lock())?;
+            std::process::exit(if clean { 0 } else { 1 });
+        }
     };
 
-    if error.is_some() {
-        let error = error.unwrap();
-        let exit_code = match error {
-            InterpreterError::InvalidCommand(_, _) | InterpreterError::InvalidFile(_) => 64,
-            InterpreterError::LexicalFailure | InterpreterError::ParserFailure(_) => 65,
-            InterpreterError::InterpreterFailure(_) => 70,
-            _ => 1,
-        };
-
-        if error != InterpreterError::LexicalFailure {
+    if let Some(error) = error {
+        // errors in the source were written by the error bag as they were
+        // found, and exit() isn't an error to report
+        if !matches!(
+            error.phase(),
+            Phase::Lexical | Phase::Syntax | Phase::Resolution
+        ) && !matches!(error, LoxError::Runtime(RuntimeError::Exit(_, _)))
+        {
             writeln!(io::stderr(), "{}", error)?;
         }
-        std::process::exit(exit_code);
+        std::process::exit(error.exit_code());
     }
 
     Ok(())
 }
 
-fn handle_args() -> Result<InterpreterCommand, InterpreterError> {
+fn handle_args() -> Result<InterpreterCommand, UsageError> {
     let args: Vec<String> = env::args().collect();
 
     //let args: Vec<String> = vec!["".into(), "tokenize".into(), "test.lox".into()];
     //let args: Vec<String> = vec!["".into(), "parse".into(), "test.lox".into()];
     //let args: Vec<String> = vec!["".into(), "interpret".into(), "test.lox".into()];
 
-    if args.len() < 3 {
+    // the language server talks over stdio, so it has no file. Editors
+    // usually start it with --stdio, which is the only transport there is.
+    if args.get(1).is_some_and(|command| command == "lsp") {
+        return match args.iter().skip(2).find(|arg| *arg != "--stdio") {
+            Some(arg) => Err(UsageError::UnknownOption(arg.clone())),
+            None => Ok(InterpreterCommand::Lsp),
+        };
+    }
+
+    // options come before the filename, e.g. `parse --format=json test.lox`,
+    // and the rest are passed on to the script
+    let mut options = Options::default();
+    let mut filename = None;
+    for arg in args.iter().skip(2) {
+        if filename.is_some() {
+            options.args.push(arg.clone());
+        } else if arg == "--trivia" {
+            options.trivia = true;
+        } else if arg == "--strict" {
+            options.strict = true;
+        } else if arg == "--trace" {
+            options.trace = true;
+        } else if arg == "--profile" {
+            options.profile = true;
+        } else if let Some(path) = arg.strip_prefix("--folded=") {
+            options.folded = Some(path.to_string());
+        } else if let Some(path) = arg.strip_prefix("--coverage=") {
+            options.coverage = Some(path.to_string());
+        } else if arg == "--allow-all" {
+            options.capabilities.extend(Capability::ALL);
+        } else if arg == "--allow-fs" {
+            options.capabilities.push(Capability::FsRead);
+            options.capabilities.push(Capability::FsWrite);
+        } else if arg == "--deny-time" {
+            options.deny_time = true;
+        } else if let Some(capability) =
+            arg.strip_prefix("--allow-").and_then(Capability::from_name)
+        {
+            options.capabilities.push(capability);
+        } else if let Some(value) = arg.strip_prefix("--max-depth=") {
+            options.limits.call_depth = parse_limit(arg, value)?;
+        } else if let Some(value) = arg.strip_prefix("--max-instructions=") {
+            options.limits.instructions = Some(parse_limit(arg, value)?);
+        } else if let Some(value) = arg.strip_prefix("--timeout=") {
+            options.limits.time = Some(Duration::from_millis(parse_limit(arg, value)?));
+        } else if let Some(value) = arg.strip_prefix("--max-heap=") {
+            options.limits.heap = Some(parse_limit(arg, value)?);
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            options.format = match value {
+                "text" => OutputFormat::Text,
+                "json" => OutputFormat::Json,
+                _ => return Err(UsageError::UnknownFormat(value.to_string())),
+            };
+        } else if arg.starts_with("--") {
+            return Err(UsageError::UnknownOption(arg.clone()));
+        } else {
+            filename = Some(arg.clone());
+        }
+    }
+
+    let Some(filename) = filename else {
         let path = Path::new(&args[0]);
         let file_name = path.to_string_lossy().into_owned();
         let empty = "command".to_owned();
         let command = args.get(1).unwrap_or(&empty);
 
-        return Err(InterpreterError::InvalidCommand(file_name, command.clone()));
+        return Err(UsageError::InvalidCommand(file_name, command.clone()));
+    };
+
+    match args[1].as_str() {
+        "tokenize" => Ok(InterpreterCommand::Tokenize(filename, options)),
+        "parse" => Ok(InterpreterCommand::Parse(filename, options)),
+        "interpret" => Ok(InterpreterCommand::Interpret(filename, options)),
+        "debug" => Ok(InterpreterCommand::Debug(filename, options)),
+        "explain" => Ok(InterpreterCommand::Explain(filename)),
+        _ => Err(UsageError::UnknownCommand(args[1].clone())),
     }
+}
 
-    return match args[1].as_str() {
-        "tokenize" => Ok(InterpreterCommand::Tokenize(args[2].clone())),
-        "parse" => Ok(InterpreterCommand::Parse(args[2].clone())),
-        "interpret" => Ok(InterpreterCommand::Interpret(args[2].clone())),
-        _ => Err(InterpreterError::UnknownCommand(args[1].clone())),
-    };
+fn parse_limit<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, UsageError> {
+    value
+        .parse()
+        .map_err(|_| UsageError::InvalidLimit(arg.to_string()))
 }
 
-fn interpret_file(filename: &String, errors: &mut ErrorBag) -> Result<(), InterpreterError> {
-    let statements = parse_file(filename, errors, false)?;
-    let mut interpreter = interpreter::Interpreter::new();
-    interpreter.interpret(&statements)?;
+fn debug_file(filename: &String, errors: &mut ErrorBag, options: &Options) -> Result<(), LoxError> {
+    let source = read_file(filename)?;
+    let input: Input = Rc::new(RefCell::new(io::stdin().lock()));
+    let debugger = Debugger::new(&source, Rc::clone(&input), Box::new(io::stdout()));
+    interpret_file(
+        filename,
+        errors,
+        options,
+        Some(Box::new(debugger)),
+        Some(input),
+    )
+}
 
+fn interpret_file(
+    filename: &String,
+    errors: &mut ErrorBag,
+    options: &Options,
+    hook: Option<Box<dyn Hook>>,
+    input: Option<Input>,
+) -> Result<(), LoxError> {
+    let statements = parse_file(filename, errors, options, false)?;
+
+    let capabilities = options
+        .capabilities
+        .iter()
+        .copied()
+        .chain([Capability::Time])
+        .filter(|capability| !(options.deny_time && *capability == Capability::Time));
+    let mut interpreter = interpreter::Interpreter::new()
+        .with_strict(options.strict)
+        .with_limits(options.limits)
+        .with_capabilities(capabilities)
+        .with_args(options.args.clone())
+        .with_script(Path::new(filename));
+    if let Some(hook) = hook {
+        interpreter = interpreter.with_hook(hook);
+    }
+    if let Some(input) = input {
+        interpreter = interpreter.with_input(input);
+    }
+
+    // the trace and profile go to stderr, out of the way of what the program prints
+    if options.trace {
+        let source = read_file(filename)?;
+        let tracer = Tracer::new(&source, &statements, Box::new(io::stderr()));
+        interpreter = interpreter.with_hook(Box::new(tracer));
+    }
+
+    let profiler = (options.profile || options.folded.is_some())
+        .then(|| Rc::new(RefCell::new(Profiler::new(&statements))));
+    if let Some(profiler) = &profiler {
+        interpreter = interpreter.with_hook(Box::new(Rc::clone(profiler)));
+    }
+
+    let coverage = options
+        .coverage
+        .is_some()
+        .then(|| Rc::new(RefCell::new(Coverage::new(filename.clone(), &statements))));
+    if let Some(coverage) = &coverage {
+        interpreter = interpreter.with_hook(Box::new(Rc::clone(coverage)));
+    }
+
+    let natives = interpreter.globals.borrow().names();
+
+    Resolver::new(errors)
+        .with_natives(natives)
+        .with_strict(options.strict)
+        .resolve(&statements);
+    if let Some(error) = errors.errors().first() {
+        return Err(error.clone());
+    }
+
+    let result = interpreter.interpret(&statements);
+
+    // a program that fails is still worth profiling
+    if let Some(profiler) = profiler {
+        let mut profiler = profiler.borrow_mut();
+        profiler.finish();
+        if options.profile {
+            let _ = profiler.write_report(&mut io::stderr());
+        }
+        if let Some(path) = &options.folded {
+            let mut file =
+                fs::File::create(path).map_err(|_| UsageError::InvalidFile(path.clone()))?;
+            profiler
+                .write_folded(&mut file)
+                .map_err(|_| UsageError::InvalidFile(path.clone()))?;
+        }
+    }
+    if let (Some(coverage), Some(path)) = (coverage, &options.coverage) {
+        let mut file = fs::File::create(path).map_err(|_| UsageError::InvalidFile(path.clone()))?;
+        coverage
+            .borrow()
+            .write_lcov(&mut file)
+            .map_err(|_| UsageError::InvalidFile(path.clone()))?;
+    }
+
+    result?;
     Ok(())
 }
 
+fn explain_code(code: &str) -> Result<(), LoxError> {
+    match error::explain(code) {
+        Some(explanation) => {
+            println!("{}", explanation);
+            Ok(())
+        }
+        None => Err(UsageError::UnknownErrorCode(code.to_string()).into()),
+    }
+}
+
 fn parse_file(
     filename: &String,
     errors: &mut ErrorBag,
+    options: &Options,
     print_tree: bool,
-) -> Result<Vec<Statement>, InterpreterError> {
-    let tokens = tokenize_file(filename, errors, false)?;
-    let mut parser = Parser::new(errors, tokens);
-    let statements = parser.parse()?;
+) -> Result<Vec<Statement>, LoxError> {
+    let source = read_file(filename)?;
+    let result = Parser::new(errors, Lexer::new(&source)).parse();
+
+    // the parser recovers from some errors, so check everything that was reported
+    if let Some(error) = errors.errors().first() {
+        return Err(error.clone());
+    }
+    let statements = result?;
 
     if print_tree {
-        let mut printer = ExpressionPrinter;
-        for statement in &statements {
-            if let Statement::Expression(expr) = statement {
-                printer.print(expr);
+        match options.format {
+            OutputFormat::Text => {
+                let mut printer = StatementPrinter;
+                for statement in &statements {
+                    println!("{}", printer.print(statement));
+                }
+            }
+            OutputFormat::Json => {
+                let tree = JsonPrinter.print(&statements);
+                println!("{}", serde_json::to_string_pretty(&tree).unwrap());
             }
         }
     }
@@ -134,14 +367,33 @@ fn parse_file(
 fn tokenize_file(
     filename: &String,
     errors: &mut ErrorBag,
-    print_tokens: bool,
-) -> Result<Vec<Token>, InterpreterError> {
-    let file_contents = fs::read_to_string(filename);
-    if file_contents.is_err() {
-        return Err(InterpreterError::InvalidFile(filename.into()));
+    options: &Options,
+) -> Result<(), LoxError> {
+    let source = read_file(filename)?;
+    let lexer = Lexer::new(&source).with_trivia(options.trivia);
+
+    let mut json = Vec::new();
+    for token in lexer {
+        match token {
+            Ok(token) => match options.format {
+                OutputFormat::Text => token.print(io::stdout().by_ref()).unwrap(),
+                OutputFormat::Json => json.push(token.to_json()),
+            },
+            Err(error) => errors.report_lex_error(error),
+        }
     }
 
-    let file_contents = file_contents.ok().unwrap_or("".into());
-    let mut lexer = Lexer::new(errors, &file_contents);
-    lexer.tokenize(print_tokens)
+    if options.format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    }
+
+    if let Some(error) = errors.errors().first() {
+        return Err(error.clone());
+    }
+
+    Ok(())
+}
+
+fn read_file(filename: &String) -> Result<String, LoxError> {
+    fs::read_to_string(filename).map_err(|_| UsageError::InvalidFile(filename.into()).into())
 }
@@ -1,34 +1,110 @@
 use dyn_clone::DynClone;
-use std::fmt::{Debug, Display};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    fs,
+    rc::Rc,
+};
 
-use crate::interpreter::{interpreter_error::InterpreterError, Interpreter};
+use crate::{
+    interpreter::{capability::Capability, runtime_error::RuntimeError, Interpreter},
+    token::Token,
+};
 
 use super::object::Object;
 
 dyn_clone::clone_trait_object!(Callable);
 
+// how many arguments a callable takes, from `min` up to `max`, or any number
+// from `min` when there is no `max`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exactly(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn at_least(count: usize) -> Self {
+        Self {
+            min: count,
+            max: None,
+        }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+// "1 argument", "1 or 2 arguments", "0 to 3 arguments" or "at least 1
+// argument", the noun agrees with the last count
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let last = self.max.unwrap_or(self.min);
+        let noun = if last == 1 { "argument" } else { "arguments" };
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{} {}", max, noun),
+            Some(max) if max == self.min + 1 => write!(f, "{} or {} {}", self.min, max, noun),
+            Some(max) => write!(f, "{} to {} {}", self.min, max, noun),
+            None => write!(f, "at least {} {}", self.min, noun),
+        }
+    }
+}
+
 pub trait Callable: Debug + Display + DynClone {
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
+
+    // what a native needs to be allowed to run
+    fn capabilities(&self) -> &'static [Capability] {
+        &[]
+    }
+
     fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Object>,
-    ) -> Result<Object, InterpreterError>;
+    ) -> Result<Object, RuntimeError>;
+
+    // a call with some of the arguments given by parameter name, which only
+    // functions declared in Lox have
+    fn call_named(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: Vec<Object>,
+        named: Vec<(Token, Object)>,
+    ) -> Result<Object, RuntimeError> {
+        Err(RuntimeError::NamedArguments(
+            self.to_string(),
+            named[0].0.span,
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Clock {}
 
 impl Callable for Clock {
-    fn arity(&self) -> usize {
-        0
+    fn arity(&self) -> Arity {
+        Arity::exactly(0)
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[Capability::Time]
     }
 
     fn call(
         &self,
         _interpreter: &mut Interpreter,
         _arguments: Vec<Object>,
-    ) -> Result<Object, InterpreterError> {
+    ) -> Result<Object, RuntimeError> {
         Ok(Object::Number(
             std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
@@ -43,3 +119,383 @@ impl Display for Clock {
         write!(f, "<fn clock>")
     }
 }
+
+// the argument at `index` as a string, or an error naming the native
+fn string_argument(
+    native: &dyn Callable,
+    interpreter: &Interpreter,
+    arguments: &[Object],
+    index: usize,
+) -> Result<String, RuntimeError> {
+    match &arguments[index] {
+        Object::String(string) => Ok(string.clone()),
+        _ => Err(RuntimeError::ArgumentType(
+            native.to_string(),
+            index + 1,
+            "a string",
+            interpreter.call_site(),
+        )),
+    }
+}
+
+fn integer_argument(
+    native: &dyn Callable,
+    interpreter: &Interpreter,
+    arguments: &[Object],
+    index: usize,
+) -> Result<i64, RuntimeError> {
+    match &arguments[index] {
+        Object::Integer(integer) => Ok(*integer),
+        _ => Err(RuntimeError::ArgumentType(
+            native.to_string(),
+            index + 1,
+            "an integer",
+            interpreter.call_site(),
+        )),
+    }
+}
+
+fn io_error(
+    interpreter: &Interpreter,
+    action: &'static str,
+    path: &str,
+    error: std::io::Error,
+) -> RuntimeError {
+    RuntimeError::Io(
+        action,
+        path.to_string(),
+        error.to_string(),
+        interpreter.call_site(),
+    )
+}
+
+fn new_list(items: Vec<Object>) -> Object {
+    Object::List(Rc::new(RefCell::new(items)))
+}
+
+// the next line from stdin, or nil once it runs out
+#[derive(Debug, Clone)]
+pub struct ReadLine {}
+
+impl Callable for ReadLine {
+    fn arity(&self) -> Arity {
+        Arity::exactly(0)
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        match interpreter.read_line() {
+            Ok(Some(line)) => Ok(Object::String(line)),
+            Ok(None) => Ok(Object::Nil),
+            Err(error) => Err(io_error(interpreter, "read", "stdin", error)),
+        }
+    }
+}
+
+impl Display for ReadLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn read_line>")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadFile {}
+
+impl Callable for ReadFile {
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[Capability::FsRead]
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let path = string_argument(self, interpreter, &arguments, 0)?;
+        fs::read_to_string(&path)
+            .map(Object::String)
+            .map_err(|error| io_error(interpreter, "read", &path, error))
+    }
+}
+
+impl Display for ReadFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn read_file>")
+    }
+}
+
+// replaces the file with the contents, printed the way print would
+#[derive(Debug, Clone)]
+pub struct WriteFile {}
+
+impl Callable for WriteFile {
+    fn arity(&self) -> Arity {
+        Arity::exactly(2)
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[Capability::FsWrite]
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let path = string_argument(self, interpreter, &arguments, 0)?;
+        fs::write(&path, arguments[1].to_string())
+            .map(|_| Object::Nil)
+            .map_err(|error| io_error(interpreter, "write", &path, error))
+    }
+}
+
+impl Display for WriteFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn write_file>")
+    }
+}
+
+// the names in a directory, sorted
+#[derive(Debug, Clone)]
+pub struct ListDir {}
+
+impl Callable for ListDir {
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[Capability::FsRead]
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let path = string_argument(self, interpreter, &arguments, 0)?;
+        let entries = fs::read_dir(&path)
+            .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+            .map_err(|error| io_error(interpreter, "list", &path, error))?;
+
+        let mut names: Vec<_> = entries
+            .iter()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        Ok(new_list(names.into_iter().map(Object::String).collect()))
+    }
+}
+
+impl Display for ListDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn list_dir>")
+    }
+}
+
+// the command line arguments after the script name
+#[derive(Debug, Clone)]
+pub struct Args {}
+
+impl Callable for Args {
+    fn arity(&self) -> Arity {
+        Arity::exactly(0)
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        _arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        Ok(new_list(
+            interpreter
+                .args()
+                .iter()
+                .cloned()
+                .map(Object::String)
+                .collect(),
+        ))
+    }
+}
+
+impl Display for Args {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn args>")
+    }
+}
+
+// stops the program, the hooks still see every call return
+#[derive(Debug, Clone)]
+pub struct Exit {}
+
+impl Callable for Exit {
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+
+    fn capabilities(&self) -> &'static [Capability] {
+        &[Capability::Process]
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let code = integer_argument(self, interpreter, &arguments, 0)?;
+        let Ok(code) = i32::try_from(code) else {
+            return Err(RuntimeError::ArgumentType(
+                self.to_string(),
+                1,
+                "a 32-bit exit code",
+                interpreter.call_site(),
+            ));
+        };
+        Err(RuntimeError::Exit(code, interpreter.call_site()))
+    }
+}
+
+impl Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn exit>")
+    }
+}
+
+// the number of characters in a string or items in a list
+#[derive(Debug, Clone)]
+pub struct Len {}
+
+impl Callable for Len {
+    fn arity(&self) -> Arity {
+        Arity::exactly(1)
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let length = match &arguments[0] {
+            Object::String(string) => string.chars().count(),
+            Object::List(list) => list.borrow().len(),
+            _ => {
+                return Err(RuntimeError::ArgumentType(
+                    self.to_string(),
+                    1,
+                    "a string or a list",
+                    interpreter.call_site(),
+                ))
+            }
+        };
+        Ok(Object::Integer(length as i64))
+    }
+}
+
+impl Display for Len {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn len>")
+    }
+}
+
+// the item of a list at an index, counting from 0
+#[derive(Debug, Clone)]
+pub struct At {}
+
+impl Callable for At {
+    fn arity(&self) -> Arity {
+        Arity::exactly(2)
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let Object::List(list) = &arguments[0] else {
+            return Err(RuntimeError::ArgumentType(
+                self.to_string(),
+                1,
+                "a list",
+                interpreter.call_site(),
+            ));
+        };
+        let index = integer_argument(self, interpreter, &arguments, 1)?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| list.borrow().get(index).cloned())
+            .ok_or(RuntimeError::IndexOutOfRange(
+                index,
+                interpreter.call_site(),
+            ))
+    }
+}
+
+impl Display for At {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn at>")
+    }
+}
+
+// the largest or smallest of any number of numbers
+#[derive(Debug, Clone)]
+pub struct Extreme {
+    name: &'static str,
+    wanted: Ordering,
+}
+
+impl Extreme {
+    pub fn max() -> Self {
+        Self {
+            name: "max",
+            wanted: Ordering::Greater,
+        }
+    }
+
+    pub fn min() -> Self {
+        Self {
+            name: "min",
+            wanted: Ordering::Less,
+        }
+    }
+}
+
+impl Callable for Extreme {
+    fn arity(&self) -> Arity {
+        Arity::at_least(1)
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let mut extreme = &arguments[0];
+        for (index, argument) in arguments.iter().enumerate() {
+            if argument.as_number().is_none() {
+                return Err(RuntimeError::ArgumentType(
+                    self.to_string(),
+                    index + 1,
+                    "a number",
+                    interpreter.call_site(),
+                ));
+            }
+            if argument.partial_cmp(extreme) == Some(self.wanted) {
+                extreme = argument;
+            }
+        }
+        Ok(extreme.clone())
+    }
+}
+
+impl Display for Extreme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn {}>", self.name)
+    }
+}
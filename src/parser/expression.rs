@@ -1,5 +1,5 @@
 use crate::{
-    token::{token_type::TokenType, token_value::TokenValue, Token},
+    token::{span::Span, token_type::TokenType, token_value::TokenValue, Token},
     visitor::expression_visitor::ExpressionVisitor,
 };
 
@@ -16,15 +16,35 @@ pub enum Expression {
         operator: Token,
         right: Box<Expression>,
     },
+    Conditional {
+        condition: Box<Expression>,
+        question: Token,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+    },
     Call {
         callee: Box<Expression>,
+        // the closing parenthesis, where errors in the call are reported
+        paren: Token,
         arguments: Vec<Expression>,
+        // `name: value` arguments, which come after the positional ones
+        named: Vec<(Token, Expression)>,
+    },
+    Get {
+        object: Box<Expression>,
+        name: Token,
     },
     Grouping {
         expression: Box<Expression>,
     },
+    // "a ${b} c", the string parts and the expressions between them in order
+    Interpolation {
+        token: Token,
+        parts: Vec<Expression>,
+    },
     Literal {
         value: Object,
+        token: Token,
     },
     Logical {
         left: Box<Expression>,
@@ -56,10 +76,38 @@ impl Expression {
         }
     }
 
-    pub fn new_call(callee: Expression, arguments: Vec<Expression>) -> Self {
+    pub fn new_call(
+        callee: Expression,
+        paren: Token,
+        arguments: Vec<Expression>,
+        named: Vec<(Token, Expression)>,
+    ) -> Self {
         Expression::Call {
             callee: Box::new(callee),
+            paren,
             arguments,
+            named,
+        }
+    }
+
+    pub fn new_conditional(
+        condition: Expression,
+        question: Token,
+        then_branch: Expression,
+        else_branch: Expression,
+    ) -> Self {
+        Expression::Conditional {
+            condition: Box::new(condition),
+            question,
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        }
+    }
+
+    pub fn new_get(object: Expression, name: Token) -> Self {
+        Expression::Get {
+            object: Box::new(object),
+            name,
         }
     }
 
@@ -69,17 +117,25 @@ impl Expression {
         }
     }
 
+    pub fn new_interpolation(token: Token, parts: Vec<Expression>) -> Self {
+        Expression::Interpolation { token, parts }
+    }
+
     pub fn new_literal(token: &Token) -> Self {
         let value = match (&token.token_type, &token.value) {
             (TokenType::Nil, _) => Object::Nil,
             (TokenType::False, _) => Object::Boolean(false),
             (TokenType::True, _) => Object::Boolean(true),
+            (_, TokenValue::Integer(num)) => Object::Integer(*num),
             (_, TokenValue::Number(num)) => Object::Number(*num),
             (_, TokenValue::String(str)) => Object::String(str.clone()),
             _ => unreachable!("Invalid token value for literal expression {:?}", token),
         };
 
-        Expression::Literal { value }
+        Expression::Literal {
+            value,
+            token: token.clone(),
+        }
     }
 
     pub fn new_logical(left: Expression, operator: Token, right: Expression) -> Self {
@@ -101,7 +157,10 @@ impl Expression {
         Expression::Variable { name }
     }
 
-    pub fn accept<T, E>(&self, visitor: &mut dyn ExpressionVisitor<T, E>) -> Result<T, E> {
+    pub fn accept<T: Default, E, V: ExpressionVisitor<T, E> + ?Sized>(
+        &self,
+        visitor: &mut V,
+    ) -> Result<T, E> {
         match self {
             Expression::Assignment { name, expression } => {
                 visitor.visit_assignment(name, expression)
@@ -111,9 +170,22 @@ impl Expression {
                 operator,
                 right,
             } => visitor.visit_binary(left, operator, right),
-            Expression::Call { callee, arguments } => visitor.visit_call(callee, arguments),
+            Expression::Call {
+                callee,
+                paren,
+                arguments,
+                named,
+            } => visitor.visit_call(callee, paren, arguments, named),
+            Expression::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => visitor.visit_conditional(condition, then_branch, else_branch),
+            Expression::Get { object, name } => visitor.visit_get(object, name),
             Expression::Grouping { expression } => visitor.visit_grouping(expression),
-            Expression::Literal { value } => visitor.visit_literal(value),
+            Expression::Interpolation { parts, .. } => visitor.visit_interpolation(parts),
+            Expression::Literal { value, .. } => visitor.visit_literal(value),
             Expression::Logical {
                 left,
                 operator,
@@ -123,4 +195,37 @@ impl Expression {
             Expression::Variable { name } => visitor.visit_variable(name),
         }
     }
+
+    pub fn line(&self) -> usize {
+        match self {
+            Expression::Assignment { name, .. } => name.line,
+            Expression::Binary { operator, .. } => operator.line,
+            Expression::Call { callee, .. } => callee.line(),
+            Expression::Conditional { question, .. } => question.line,
+            Expression::Get { name, .. } => name.line,
+            Expression::Grouping { expression } => expression.line(),
+            Expression::Interpolation { token, .. } => token.line,
+            Expression::Literal { token, .. } => token.line,
+            Expression::Logical { operator, .. } => operator.line,
+            Expression::Unary { operator, .. } => operator.line,
+            Expression::Variable { name } => name.line,
+        }
+    }
+
+    // the span of the same token line() reports
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Assignment { name, .. } => name.span,
+            Expression::Binary { operator, .. } => operator.span,
+            Expression::Call { callee, .. } => callee.span(),
+            Expression::Conditional { question, .. } => question.span,
+            Expression::Get { name, .. } => name.span,
+            Expression::Grouping { expression } => expression.span(),
+            Expression::Interpolation { token, .. } => token.span,
+            Expression::Literal { token, .. } => token.span,
+            Expression::Logical { operator, .. } => operator.span,
+            Expression::Unary { operator, .. } => operator.span,
+            Expression::Variable { name } => name.span,
+        }
+    }
 }
@@ -5,25 +5,29 @@ use std::{
 };
 
 use crate::{
-    interpreter::{environment, interpreter_error::InterpreterError, Interpreter},
+    interpreter::{environment, runtime_error::RuntimeError, Interpreter},
     token::{token_value::TokenValue, Token},
 };
 
-use super::{callable::Callable, object::Object, statement::Statement};
+use super::{
+    callable::{Arity, Callable},
+    object::Object,
+    statement::{Parameter, Statement},
+};
 
 #[derive(Debug, Clone)]
 pub struct Function {
     name: String,
-    params: Vec<Token>,
-    body: Vec<Statement>,
+    params: Rc<[Parameter]>,
+    body: Rc<[Statement]>,
     closure: Option<Rc<RefCell<environment::Environment>>>,
 }
 
 impl Function {
     pub fn new(
         name: String,
-        params: Vec<Token>,
-        body: Vec<Statement>,
+        params: Rc<[Parameter]>,
+        body: Rc<[Statement]>,
         closure: Option<Rc<RefCell<environment::Environment>>>,
     ) -> Self {
         Self {
@@ -36,24 +40,99 @@ impl Function {
 }
 
 impl Callable for Function {
-    fn arity(&self) -> usize {
-        self.params.len()
+    // parameters with defaults are optional, and a rest parameter takes any
+    // number of arguments
+    fn arity(&self) -> Arity {
+        let required = self
+            .params
+            .iter()
+            .filter(|param| param.default.is_none() && !param.rest)
+            .count();
+        let positional = self.params.iter().filter(|param| !param.rest).count();
+        Arity {
+            min: required,
+            max: if self.params.iter().any(|param| param.rest) {
+                None
+            } else {
+                Some(positional)
+            },
+        }
     }
 
     fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Object>,
-    ) -> Result<Object, InterpreterError> {
+    ) -> Result<Object, RuntimeError> {
+        self.call_named(interpreter, arguments, Vec::new())
+    }
+
+    fn call_named(
+        &self,
+        interpreter: &mut Interpreter,
+        mut arguments: Vec<Object>,
+        named: Vec<(Token, Object)>,
+    ) -> Result<Object, RuntimeError> {
+        // defaults can make calls of their own
+        let call_site = interpreter.call_site();
+        let arity = self.arity();
+        if arity.max.is_some_and(|max| arguments.len() > max) {
+            return Err(RuntimeError::ArityMismatch(
+                arity,
+                arguments.len() + named.len(),
+                call_site,
+            ));
+        }
+
+        // one slot for every parameter but the rest parameter, which is last
+        // and gets the positional arguments left over
+        let positional = self.params.iter().filter(|param| !param.rest).count();
+        let rest = arguments.split_off(arguments.len().min(positional));
+        let mut values: Vec<Option<Object>> = arguments.into_iter().map(Some).collect();
+        values.resize(positional, None);
+
+        for (name, value) in named {
+            let Some(index) = self
+                .params
+                .iter()
+                .position(|param| !param.rest && param.name.value == name.value)
+            else {
+                return Err(RuntimeError::UnknownArgument(
+                    name.value.to_string(),
+                    name.span,
+                ));
+            };
+            if values[index].is_some() {
+                return Err(RuntimeError::DuplicateArgument(
+                    name.value.to_string(),
+                    name.span,
+                ));
+            }
+            values[index] = Some(value);
+        }
+
         let environment = Rc::new(RefCell::new(environment::Environment::new(Some(
-            Rc::clone(&self.closure.as_ref().unwrap()),
+            Rc::clone(self.closure.as_ref().unwrap()),
         ))));
 
-        for (param, argument) in self.params.iter().zip(arguments.iter()) {
-            if let TokenValue::Identifier(name) = &param.value {
-                environment
-                    .borrow_mut()
-                    .define(name.as_str(), argument.clone());
+        let mut values = values.into_iter();
+        let mut rest = Some(rest);
+        for param in self.params.iter() {
+            let value = if param.rest {
+                Object::List(Rc::new(RefCell::new(rest.take().unwrap_or_default())))
+            } else if let Some(value) = values.next().flatten() {
+                value
+            } else if let Some(default) = &param.default {
+                interpreter.evaluate_in(default, Rc::clone(&environment))?
+            } else {
+                return Err(RuntimeError::MissingArgument(
+                    param.name.value.to_string(),
+                    call_site,
+                ));
+            };
+
+            if let TokenValue::Identifier(name) = &param.name.value {
+                environment.borrow_mut().define(name.as_str(), value);
             }
         }
 
@@ -1,16 +1,20 @@
 pub mod callable;
 pub mod expression;
 pub mod function;
+pub mod module;
 pub mod object;
 pub mod parse_error;
 pub mod statement;
 
 use expression::Expression;
 use parse_error::ParseError;
-use statement::Statement;
+use statement::{Parameter, Statement};
+
+use std::collections::VecDeque;
 
 use crate::{
     error_bag::ErrorBag,
+    lexer::lex_error::LexError,
     token::{token_type::TokenType, Token},
 };
 
@@ -27,17 +31,25 @@ macro_rules! match_tokens {
     }};
 }
 
+// Tokens are pulled from the lexer as they are needed and only the few
+// the grammar looks ahead at are buffered. Lexical errors met along the
+// way are reported and the offending characters skipped.
 pub struct Parser<'a> {
-    tokens: Vec<Token>,
-    current: usize,
+    tokens: Box<dyn Iterator<Item = Result<Token, LexError>> + 'a>,
+    lookahead: VecDeque<Token>,
+    previous: Option<Token>,
     errors: &'a mut ErrorBag,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(errors: &'a mut ErrorBag, tokens: Vec<Token>) -> Self {
+    pub fn new(
+        errors: &'a mut ErrorBag,
+        tokens: impl IntoIterator<Item = Result<Token, LexError>> + 'a,
+    ) -> Self {
         Parser {
-            tokens,
-            current: 0,
+            tokens: Box::new(tokens.into_iter()),
+            lookahead: VecDeque::new(),
+            previous: None,
             errors,
         }
     }
@@ -46,20 +58,30 @@ impl<'a> Parser<'a> {
         let mut statements: Vec<Statement> = Vec::new();
 
         while !self.is_at_end() {
-            let stmt = self.declaration()?;
-            statements.push(stmt);
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    // still report every lexical error in the source, without
+                    // keeping the tokens
+                    self.lookahead.clear();
+                    for token in self.tokens.by_ref() {
+                        if let Err(error) = token {
+                            self.errors.report_lex_error(error);
+                        }
+                    }
+                    return Err(err);
+                }
+            }
         }
 
         Ok(statements)
     }
 
     fn advance(&mut self) -> Option<&Token> {
-        if let Some(token) = self.tokens.get(self.current) {
-            self.current += 1;
-            return Some(token);
-        }
-
-        None
+        self.fill(1);
+        let token = self.lookahead.pop_front()?;
+        self.previous = Some(token);
+        self.previous.as_ref()
     }
 
     fn and(&mut self) -> Result<Expression, ParseError> {
@@ -74,13 +96,35 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    // a += b is desugared into a = a + b
     fn assignment(&mut self) -> Result<Expression, ParseError> {
-        let expr = self.or()?;
-        if match_tokens!(self, TokenType::Equal) {
+        let expr = self.conditional()?;
+        if match_tokens!(
+            self,
+            TokenType::Equal,
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual
+        ) {
             let equals = self.previous().unwrap().clone();
-            let value = self.assignment()?;
+            let mut value = self.assignment()?;
 
             if let Expression::Variable { name } = expr {
+                let operator = match equals.token_type {
+                    TokenType::PlusEqual => Some(TokenType::Plus),
+                    TokenType::MinusEqual => Some(TokenType::Minus),
+                    TokenType::StarEqual => Some(TokenType::Star),
+                    TokenType::SlashEqual => Some(TokenType::Slash),
+                    _ => None,
+                };
+                if let Some(operator) = operator {
+                    let operator =
+                        Token::from_token_type(equals.line, operator).with_span(equals.span);
+                    let target = Expression::new_variable(name.clone());
+                    value = Expression::new_binary(target, operator, value);
+                }
+
                 return Ok(Expression::new_assignment(name, value));
             }
 
@@ -89,7 +133,43 @@ impl<'a> Parser<'a> {
             // we need to go into panic mode and synchronize.
             let _ = self
                 .errors
-                .parse_error(&equals, "Invalid assignment target.");
+                .parse_error(ParseError::InvalidAssignmentTarget(Box::new(equals)));
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_and(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.shift()?;
+
+        while match_tokens!(self, TokenType::Ampersand) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.shift()?;
+            expr = Expression::new_binary(expr, operator, right);
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_or(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.bitwise_xor()?;
+
+        while match_tokens!(self, TokenType::Pipe) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.bitwise_xor()?;
+            expr = Expression::new_binary(expr, operator, right);
+        }
+
+        Ok(expr)
+    }
+
+    fn bitwise_xor(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.bitwise_and()?;
+
+        while match_tokens!(self, TokenType::Caret) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.bitwise_and()?;
+            expr = Expression::new_binary(expr, operator, right);
         }
 
         Ok(expr)
@@ -116,6 +196,10 @@ impl<'a> Parser<'a> {
         loop {
             if match_tokens!(self, TokenType::LeftParen) {
                 expr = self.finish_call(expr)?;
+            } else if match_tokens!(self, TokenType::Dot) {
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expression::new_get(expr, name.unwrap().clone());
             } else {
                 break;
             }
@@ -124,7 +208,7 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    fn check(&self, token_type: TokenType) -> bool {
+    fn check(&mut self, token_type: TokenType) -> bool {
         if let Some(next_token) = self.peek() {
             return next_token.token_type == token_type;
         }
@@ -132,8 +216,18 @@ impl<'a> Parser<'a> {
         false
     }
 
+    // whether the token after the next one has the type
+    fn check_next(&mut self, token_type: TokenType) -> bool {
+        self.fill(2);
+        self.lookahead
+            .get(1)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
+    // the bitwise operators bind tighter than comparisons, so a & 1 == 0
+    // compares the result of the &
     fn comparison(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = self.term()?;
+        let mut expr = self.bitwise_or()?;
 
         while match_tokens!(
             self,
@@ -143,7 +237,7 @@ impl<'a> Parser<'a> {
             TokenType::LessEqual
         ) {
             let operator = self.previous().unwrap().clone();
-            let right = self.term()?;
+            let right = self.bitwise_or()?;
             let left = expr;
             expr = Expression::new_binary(left, operator, right);
         }
@@ -151,6 +245,57 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    // a ?? b is a logical expression, it only evaluates b when a is nil
+    fn coalesce(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.or()?;
+
+        while match_tokens!(self, TokenType::QuestionQuestion) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.or()?;
+            expr = Expression::new_logical(expr, operator, right);
+        }
+
+        Ok(expr)
+    }
+
+    // c ? a : b, which nests to the right so a ? b : c ? d : e needs no parentheses
+    fn conditional(&mut self) -> Result<Expression, ParseError> {
+        let condition = self.coalesce()?;
+
+        if match_tokens!(self, TokenType::Question) {
+            let question = self.previous().unwrap().clone();
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expression::new_conditional(
+                condition,
+                question,
+                then_branch,
+                else_branch,
+            ));
+        }
+
+        Ok(condition)
+    }
+
+    // const <name> = <expression>;
+    fn constant_declaration(&mut self) -> Result<Statement, ParseError> {
+        let name = self.consume(TokenType::Identifier, "Expect constant name.")?;
+        let name = name.unwrap().clone();
+
+        self.consume(TokenType::Equal, "Expect '=' after constant name.")?;
+        let initializer = self.expression()?;
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after constant declaration.",
+        )?;
+
+        Ok(Statement::Constant(name, initializer))
+    }
+
     fn consume(
         &mut self,
         token_type: TokenType,
@@ -164,7 +309,10 @@ impl<'a> Parser<'a> {
             Some(token) => token.clone(),
             None => Token::new_eof(0),
         };
-        Err(self.errors.parse_error(&token, message))
+        Err(self.errors.parse_error(ParseError::UnexpectedToken(
+            Box::new(token),
+            message.to_string(),
+        )))
     }
 
     fn declaration(&mut self) -> Result<Statement, ParseError> {
@@ -172,6 +320,10 @@ impl<'a> Parser<'a> {
             self.function("function")
         } else if match_tokens!(self, TokenType::Var) {
             self.variable_declaration()
+        } else if match_tokens!(self, TokenType::Const) {
+            self.constant_declaration()
+        } else if match_tokens!(self, TokenType::Import, TokenType::From) {
+            self.import_declaration()
         } else {
             self.statement()
         }
@@ -215,37 +367,69 @@ impl<'a> Parser<'a> {
             format!("Expect '(' after {kind} name.").as_str(),
         );
 
-        let mut parameters = Vec::new();
+        let mut parameters: Vec<Parameter> = Vec::new();
+        let mut rest = false;
         if !self.check(TokenType::RightParen) {
             loop {
                 if parameters.len() >= 255 {
                     let token = self.peek().unwrap().clone();
                     self.errors
-                        .parse_error(&token, "Can't have more than 255 parameters.");
+                        .parse_error(ParseError::TooManyItems(Box::new(token), "parameters"));
                 }
 
-                let param = self.consume(TokenType::Identifier, "Expect parameter name.")?;
-                parameters.push(param.unwrap().clone());
+                rest = match_tokens!(self, TokenType::DotDotDot);
+                let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                let name = name.unwrap().clone();
+
+                let default = if !rest && match_tokens!(self, TokenType::Equal) {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                // once one parameter is optional, the ones after it are too
+                let optional = parameters.iter().any(|param| param.default.is_some());
+                if optional && default.is_none() && !rest {
+                    self.errors.parse_error(ParseError::UnexpectedToken(
+                        Box::new(name.clone()),
+                        "Expect a default value after an optional parameter.".to_string(),
+                    ));
+                }
 
-                if !match_tokens!(self, TokenType::Comma) {
+                parameters.push(Parameter {
+                    name,
+                    default,
+                    rest,
+                });
+                if rest || !match_tokens!(self, TokenType::Comma) {
                     break;
                 }
             }
         }
 
-        let _ = self.consume(TokenType::RightParen, "Expect ')' after parameters.");
+        let message = if rest {
+            "Expect ')' after rest parameter."
+        } else {
+            "Expect ')' after parameters."
+        };
+        let _ = self.consume(TokenType::RightParen, message);
         let _ = self.consume(
             TokenType::LeftBrace,
             format!("Expect '{{' before {kind} body.").as_str(),
         );
         let body = self.block()?;
-        Ok(Statement::Function(name, parameters, body))
+        Ok(Statement::Function(name, parameters.into(), body.into()))
     }
 
     fn factor(&mut self) -> Result<Expression, ParseError> {
         let mut expr = self.unary()?;
 
-        while match_tokens!(self, TokenType::Slash, TokenType::Star) {
+        while match_tokens!(
+            self,
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash
+        ) {
             let left = expr;
             let operator = self.previous().unwrap().clone();
             let right = self.unary()?;
@@ -257,6 +441,7 @@ impl<'a> Parser<'a> {
 
     // for ( <initializer>; <condition>; <increment>) { <body> }
     fn for_statement(&mut self) -> Result<Statement, ParseError> {
+        let keyword = self.previous().unwrap().clone();
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'for'.");
 
         // first we handle <initializer>
@@ -271,7 +456,8 @@ impl<'a> Parser<'a> {
 
         // next, the <condition>
         // a missing condition's value is true
-        let mut condition = Expression::new_literal(&Token::from_token_type(0, TokenType::True));
+        let mut condition =
+            Expression::new_literal(&Token::from_token_type(keyword.line, TokenType::True));
         if !self.check(TokenType::Semicolon) {
             condition = self.expression()?;
         }
@@ -291,16 +477,19 @@ impl<'a> Parser<'a> {
         // working backward, we place the increment expression at the bottom
         // of the block that contains the body
         if let Some(increment) = increment {
-            body = Statement::Block(vec![body, Statement::Expression(increment)])
+            body = Statement::Block(
+                keyword.clone(),
+                vec![body, Statement::Expression(increment)],
+            )
         }
 
         // wrap the condition an new body inside of a while statement
-        body = Statement::While(condition, Box::new(body));
+        body = Statement::While(keyword.clone(), condition, Box::new(body));
 
         // finally, if there is an initializer, place it at the
         // head of the body block
         if let Some(initializer) = initializer {
-            body = Statement::Block(vec![initializer, body])
+            body = Statement::Block(keyword, vec![initializer, body])
         }
 
         Ok(body)
@@ -308,16 +497,30 @@ impl<'a> Parser<'a> {
 
     fn finish_call(&mut self, callee: Expression) -> Result<Expression, ParseError> {
         let mut arguments: Vec<Expression> = Vec::new();
+        let mut named: Vec<(Token, Expression)> = Vec::new();
 
         if !self.check(TokenType::RightParen) {
             loop {
-                if arguments.len() >= 255 {
+                if arguments.len() + named.len() >= 255 {
                     let token = self.peek().unwrap().clone();
                     self.errors
-                        .parse_error(&token, "Can't have more than 255 arguments.");
+                        .parse_error(ParseError::TooManyItems(Box::new(token), "arguments"));
                 }
 
-                arguments.push(self.expression()?);
+                // `name: value` binds the argument to the parameter with that name
+                if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon) {
+                    let name = self.advance().unwrap().clone();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                } else if !named.is_empty() {
+                    let token = self.peek().unwrap().clone();
+                    return Err(self.errors.parse_error(ParseError::UnexpectedToken(
+                        Box::new(token),
+                        "Expect a named argument after named arguments.".to_string(),
+                    )));
+                } else {
+                    arguments.push(self.expression()?);
+                }
 
                 if !match_tokens!(self, TokenType::Comma) {
                     break;
@@ -325,12 +528,16 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let _ = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        let paren = self
+            .consume(TokenType::RightParen, "Expect ')' after arguments.")?
+            .unwrap()
+            .clone();
 
-        Ok(Expression::new_call(callee, arguments))
+        Ok(Expression::new_call(callee, paren, arguments, named))
     }
 
     fn if_statement(&mut self) -> Result<Statement, ParseError> {
+        let keyword = self.previous().unwrap().clone();
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'if'.");
         let condition = self.expression()?;
         let _ = self.consume(TokenType::RightParen, "Expect ')' after if condition.");
@@ -342,10 +549,72 @@ impl<'a> Parser<'a> {
             else_branch = Some(Box::new(stmt));
         }
 
-        Ok(Statement::If(condition, Box::new(then_branch), else_branch))
+        Ok(Statement::If(
+            keyword,
+            condition,
+            Box::new(then_branch),
+            else_branch,
+        ))
+    }
+
+    // import "<path>" as <name>;
+    // from "<path>" import <name> [, <name>]*;
+    fn import_declaration(&mut self) -> Result<Statement, ParseError> {
+        let keyword = self.previous().unwrap().clone();
+        let path = self.consume(TokenType::String, "Expect module path.")?;
+        let path = path.unwrap().clone();
+
+        let mut alias = None;
+        let mut names = Vec::new();
+        if keyword.token_type == TokenType::Import {
+            self.consume(TokenType::As, "Expect 'as' after module path.")?;
+            let name = self.consume(TokenType::Identifier, "Expect module name.")?;
+            alias = Some(name.unwrap().clone());
+        } else {
+            self.consume(TokenType::Import, "Expect 'import' after module path.")?;
+            loop {
+                let name = self.consume(TokenType::Identifier, "Expect name to import.")?;
+                names.push(name.unwrap().clone());
+
+                if !match_tokens!(self, TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Statement::Import(keyword, path, alias, names))
+    }
+
+    // "a ${b} c" becomes an interpolation of "a ", b and " c"
+    fn interpolation(&mut self) -> Result<Expression, ParseError> {
+        let token = self.previous().unwrap().clone();
+        let mut parts = vec![Expression::new_literal(&token)];
+
+        loop {
+            parts.push(self.expression()?);
+
+            if !match_tokens!(self, TokenType::Interpolation, TokenType::String) {
+                let token = self.peek().unwrap().clone();
+                return Err(self.errors.parse_error(ParseError::UnexpectedToken(
+                    Box::new(token),
+                    "Expect end of string interpolation.".to_string(),
+                )));
+            }
+
+            let part = self.previous().unwrap().clone();
+            parts.push(Expression::new_literal(&part));
+            if part.token_type == TokenType::String {
+                return Ok(Expression::new_interpolation(token, parts));
+            }
+        }
+    }
+
+    fn fill(&mut self, count: usize) {
+        while self.lookahead.len() < count && self.pull() {}
     }
 
-    fn is_at_end(&self) -> bool {
+    fn is_at_end(&mut self) -> bool {
         if let Some(token) = self.peek() {
             token.token_type == TokenType::Eof
         } else {
@@ -358,19 +627,34 @@ impl<'a> Parser<'a> {
 
         while match_tokens!(self, TokenType::Or) {
             let operator = self.previous().unwrap().clone();
-            let right = self.expression()?;
+            let right = self.and()?;
             expr = Expression::new_logical(expr, operator, right);
         }
 
         Ok(expr)
     }
 
-    fn peek(&self) -> Option<&Token> {
-        self.tokens.get(self.current)
+    fn peek(&mut self) -> Option<&Token> {
+        self.fill(1);
+        self.lookahead.front()
+    }
+
+    // ** is right associative and binds tighter than a unary on its left,
+    // so -2 ** 2 is -4 and 2 ** -1 is 0.5
+    fn power(&mut self) -> Result<Expression, ParseError> {
+        let expr = self.call()?;
+
+        if match_tokens!(self, TokenType::StarStar) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.unary()?;
+            return Ok(Expression::new_binary(expr, operator, right));
+        }
+
+        Ok(expr)
     }
 
     fn previous(&self) -> Option<&Token> {
-        self.tokens.get(self.current - 1)
+        self.previous.as_ref()
     }
 
     fn primary(&mut self) -> Result<Expression, ParseError> {
@@ -387,6 +671,10 @@ impl<'a> Parser<'a> {
             return Ok(expr);
         }
 
+        if match_tokens!(self, TokenType::Interpolation) {
+            return self.interpolation();
+        }
+
         if match_tokens!(self, TokenType::Identifier) {
             let token = self.previous().unwrap().clone();
             let expr = Expression::new_variable(token);
@@ -407,29 +695,55 @@ impl<'a> Parser<'a> {
             Some(token) => token.clone(),
             None => Token::new_eof(0),
         };
-        Err(self.errors.parse_error(&token, "Expect expression."))
+        Err(self
+            .errors
+            .parse_error(ParseError::ExpectedExpression(Box::new(token))))
     }
 
     fn print_statement(&mut self) -> Result<Statement, ParseError> {
+        let keyword = self.previous().unwrap().clone();
         let expr = self.expression()?;
         if let Err(err) = self.consume(TokenType::Semicolon, "Expect ';' after value.") {
             Err(err)
         } else {
-            Ok(Statement::Print(expr))
+            Ok(Statement::Print(keyword, expr))
+        }
+    }
+
+    // reads the next token from the lexer into the lookahead,
+    // returns false once the lexer has nothing left
+    fn pull(&mut self) -> bool {
+        match self.tokens.next() {
+            Some(Ok(token)) => self.lookahead.push_back(token),
+            Some(Err(error)) => self.errors.report_lex_error(error),
+            None => return false,
         }
+        true
     }
 
     fn return_statement(&mut self) -> Result<Statement, ParseError> {
-        let _ = self.previous().unwrap().clone();
+        let keyword = self.previous().unwrap().clone();
 
         let mut value = None;
-        if !match_tokens!(self, TokenType::Semicolon) {
+        if !self.check(TokenType::Semicolon) {
             let result = self.expression();
             value = Some(result?);
         }
 
         let _ = self.consume(TokenType::Semicolon, "Expect ';' after return value.");
-        Ok(Statement::Return(value))
+        Ok(Statement::Return(keyword, value))
+    }
+
+    fn shift(&mut self) -> Result<Expression, ParseError> {
+        let mut expr = self.term()?;
+
+        while match_tokens!(self, TokenType::LessLess, TokenType::GreaterGreater) {
+            let operator = self.previous().unwrap().clone();
+            let right = self.term()?;
+            expr = Expression::new_binary(expr, operator, right);
+        }
+
+        Ok(expr)
     }
 
     fn statement(&mut self) -> Result<Statement, ParseError> {
@@ -444,8 +758,9 @@ impl<'a> Parser<'a> {
         } else if match_tokens!(self, TokenType::While) {
             self.while_statement()
         } else if match_tokens!(self, TokenType::LeftBrace) {
+            let brace = self.previous().unwrap().clone();
             let block = self.block()?;
-            Ok(Statement::Block(block))
+            Ok(Statement::Block(brace, block))
         } else {
             self.expression_statement()
         }
@@ -463,6 +778,9 @@ impl<'a> Parser<'a> {
                 TokenType::Class
                 | TokenType::Fun
                 | TokenType::Var
+                | TokenType::Const
+                | TokenType::Import
+                | TokenType::From
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
@@ -489,14 +807,14 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> Result<Expression, ParseError> {
-        if match_tokens!(self, TokenType::Bang, TokenType::Minus) {
+        if match_tokens!(self, TokenType::Bang, TokenType::Minus, TokenType::Tilde) {
             let operator = self.previous().unwrap().clone();
             let right = self.unary()?;
             let expr = Expression::new_unary(operator, right);
             return Ok(expr);
         }
 
-        self.call()
+        self.power()
     }
 
     // var <name> [ = <expression> ];
@@ -518,11 +836,103 @@ impl<'a> Parser<'a> {
 
     // while (<condition>) { <body> }
     fn while_statement(&mut self) -> Result<Statement, ParseError> {
+        let keyword = self.previous().unwrap().clone();
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'while'.");
         let condition = self.expression()?;
         let _ = self.consume(TokenType::RightParen, "Expect ')' after condition.");
         let body = self.statement()?;
 
-        Ok(Statement::While(condition, Box::new(body)))
+        Ok(Statement::While(keyword, condition, Box::new(body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::LoxError, lexer::Lexer, visitor::statement_printer::StatementPrinter};
+    use rstest::*;
+
+    fn print(source: &str) -> String {
+        let mut errors = ErrorBag::default();
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+        StatementPrinter.print(&statements[0])
+    }
+
+    #[rstest]
+    #[case("-2 ** 2;", "(- (** 2 2))")]
+    #[case("2 ** 3 ** 2;", "(** 2 (** 3 2))")]
+    #[case("2 ** -1;", "(** 2 (- 1))")]
+    #[case("a * b % c ~/ d;", "(~/ (% (* a b) c) d)")]
+    #[case("1 | 2 ^ 3 & 4 << 5 + 6;", "(| 1 (^ 2 (& 3 (<< 4 (+ 5 6)))))")]
+    #[case("a & 1 == 0;", "(== (& a 1) 0)")]
+    #[case("~a >> 1;", "(>> (~ a) 1)")]
+    #[case("a += 1;", "(set a (+ a 1))")]
+    #[case("a -= b *= 2;", "(set a (- a (set b (* b 2))))")]
+    #[case("a /= 2;", "(set a (/ a 2))")]
+    #[case("a ? b : c;", "(?: a b c)")]
+    #[case("a ? b : c ? d : e;", "(?: a b (?: c d e))")]
+    #[case("a ? b ? c : d : e;", "(?: a (?: b c d) e)")]
+    #[case("x = a ?? b ? c : d;", "(set x (?: (?? a b) c d))")]
+    #[case("a ?? b ?? c or d;", "(?? (?? a b) (or c d))")]
+    #[case("a or b ? c : d;", "(?: (or a b) c d)")]
+    #[case("a or b ?? c;", "(?? (or a b) c)")]
+    #[case("a or b or c;", "(or (or a b) c)")]
+    #[case("\"a ${b + 1} c\";", "(interpolate \"a \" (+ b 1) \" c\")")]
+    #[case("\"${a}${b}\";", "(interpolate \"\" a \"\" b \"\")")]
+    fn test_operator_precedence(#[case] source: &str, #[case] expected: &str) {
+        assert_eq!(print(source), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "fun f(a = 1, b) {}",
+        "Expect a default value after an optional parameter."
+    )]
+    #[case("fun f(...a, b) {}", "Expect ')' after rest parameter.")]
+    #[case("f(a: 1, 2);", "Expect a named argument after named arguments.")]
+    fn test_invalid_parameters_and_arguments(#[case] source: &str, #[case] message: &str) {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let _ = Parser::new(&mut errors, Lexer::new(source)).parse();
+        assert_eq!(errors.errors()[0].message(), message);
+    }
+
+    #[test]
+    fn test_lex_errors_after_syntax_error() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let mut parser = Parser::new(&mut errors, Lexer::new("print 1 +;\nvar a = @;\nprint a;"));
+        assert!(parser.parse().is_err());
+        // the rest of the source is drained, not buffered
+        assert!(parser.lookahead.is_empty());
+        drop(parser);
+        assert!(matches!(
+            errors.errors(),
+            [
+                LoxError::Syntax(ParseError::ExpectedExpression(_)),
+                LoxError::Lexical(LexError::UnexpectedCharacter('@', _))
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_no_syntax_errors_after_lex_error() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let result = Parser::new(&mut errors, Lexer::new("var a = @;\nprint a +;")).parse();
+        assert!(result.is_err());
+        assert!(matches!(
+            errors.errors(),
+            [LoxError::Lexical(LexError::UnexpectedCharacter('@', _))]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_compound_assignment_target() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let _ = Parser::new(&mut errors, Lexer::new("a + b += 1;")).parse();
+        assert!(matches!(
+            errors.errors(),
+            [LoxError::Syntax(ParseError::InvalidAssignmentTarget(_))]
+        ));
     }
 }
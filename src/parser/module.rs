@@ -0,0 +1,44 @@
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter},
+    rc::Rc,
+};
+
+use crate::{
+    interpreter::{environment::Environment, runtime_error::RuntimeError},
+    token::Token,
+};
+
+use super::object::Object;
+
+// An imported file. Its members are the globals it declared, so a module
+// only has to run once however many files import it.
+#[derive(Debug)]
+pub struct Module {
+    name: String,
+    globals: Rc<RefCell<Environment>>,
+}
+
+impl Module {
+    pub fn new(name: String, globals: Rc<RefCell<Environment>>) -> Self {
+        Self { name, globals }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Object, RuntimeError> {
+        let globals = self.globals.borrow();
+        if !globals.contains(&name.value.to_string()) {
+            return Err(RuntimeError::UndefinedProperty(
+                name.value.to_string(),
+                name.span,
+            ));
+        }
+
+        globals.get(name)
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
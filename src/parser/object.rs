@@ -1,13 +1,17 @@
-use std::{cmp::Ordering, fmt::Display};
+use std::{cell::RefCell, cmp::Ordering, fmt::Display, rc::Rc};
 
-use super::callable::Callable;
+use super::{callable::Callable, module::Module};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Object {
+    Integer(i64),
     Number(f64),
     String(String),
     Boolean(bool),
     Callable(Box<dyn Callable>),
+    Module(Rc<Module>),
+    List(Rc<RefCell<Vec<Object>>>),
+    #[default]
     Nil,
 }
 
@@ -23,6 +27,26 @@ impl Object {
     pub fn is_callable(&self) -> bool {
         matches!(self, Object::Callable(_))
     }
+
+    // the value of an integer or a float as a float
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Object::Integer(num) => Some(*num as f64),
+            Object::Number(num) => Some(*num),
+            _ => None,
+        }
+    }
+
+    // roughly the bytes the value holds, functions and modules count their
+    // variables in their own environments
+    pub fn size(&self) -> usize {
+        let contents = match self {
+            Object::String(string) => string.len(),
+            Object::List(list) => list.borrow().iter().map(Object::size).sum(),
+            _ => 0,
+        };
+        std::mem::size_of::<Object>() + contents
+    }
 }
 
 impl PartialEq for Object {
@@ -31,9 +55,15 @@ impl PartialEq for Object {
             (Self::Nil, Self::Nil) => true,
             (Self::Nil, _) => false,
             (_, Self::Nil) => false,
-            (Self::Number(left), Self::Number(right)) => left == right,
+            (Self::Integer(left), Self::Integer(right)) => left == right,
+            (Self::Integer(_) | Self::Number(_), Self::Integer(_) | Self::Number(_)) => {
+                self.as_number() == other.as_number()
+            }
             (Self::String(left), Self::String(right)) => left == right,
             (Self::Boolean(left), Self::Boolean(right)) => left == right,
+            // a module is only loaded once, so it is only equal to itself
+            (Self::Module(left), Self::Module(right)) => Rc::ptr_eq(left, right),
+            (Self::List(left), Self::List(right)) => Rc::ptr_eq(left, right),
             // this might need to just be false
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
@@ -44,14 +74,9 @@ impl PartialOrd for Object {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
             (Self::Nil, Self::Nil) => Some(Ordering::Equal),
-            (Self::Number(left), Self::Number(right)) => {
-                if left < right {
-                    Some(Ordering::Less)
-                } else if left > right {
-                    Some(Ordering::Greater)
-                } else {
-                    Some(Ordering::Equal)
-                }
+            (Self::Integer(left), Self::Integer(right)) => Some(left.cmp(right)),
+            (Self::Integer(_) | Self::Number(_), Self::Integer(_) | Self::Number(_)) => {
+                self.as_number().partial_cmp(&other.as_number())
             }
             (Self::String(left), Self::String(right)) => match left.cmp(right) {
                 Ordering::Less => Some(Ordering::Less),
@@ -73,17 +98,54 @@ impl PartialOrd for Object {
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Object::Number(num) => {
-                if num.fract() == 0.0 {
-                    write!(f, "{}", *num as i64)
+            Object::Integer(num) => write!(f, "{}", num),
+            // the shortest form that reads back as the same float: whole floats
+            // keep a ".0", or use an exponent once they get long
+            Object::Number(num) if num.is_finite() && num.trunc() == *num => {
+                if num.abs() < 1e16 {
+                    write!(f, "{:.1}", num)
                 } else {
-                    write!(f, "{}", num)
+                    write!(f, "{:e}", num)
                 }
             }
+            Object::Number(num) => write!(f, "{}", num),
             Object::String(str) => write!(f, "{}", str),
             Object::Boolean(bool) => write!(f, "{}", bool),
             Object::Callable(callable) => write!(f, "{}", callable),
+            Object::Module(module) => write!(f, "{}", module),
+            Object::List(list) => {
+                write!(f, "[")?;
+                for (index, item) in list.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
             Object::Nil => write!(f, "nil"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, token::token_value::TokenValue};
+    use rstest::*;
+
+    #[rstest]
+    #[case(1.0, "1.0")]
+    #[case(0.5, "0.5")]
+    #[case(1e15, "1000000000000000.0")]
+    #[case(1e20, "1e20")]
+    #[case(1.5e300, "1.5e300")]
+    #[case(1e-7, "0.0000001")]
+    fn test_number_round_trip(#[case] value: f64, #[case] expected: &str) {
+        let printed = Object::Number(value).to_string();
+        assert_eq!(printed, expected);
+
+        let token = Lexer::new(&printed).next().unwrap().unwrap();
+        assert_eq!(token.value, TokenValue::Number(value));
+    }
+}
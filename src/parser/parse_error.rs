@@ -1,20 +1,37 @@
-use crate::token::token_type::TokenType;
+use crate::token::{token_type::TokenType, Token};
 
-#[derive(thiserror::Error, Debug, PartialEq)]
+// Each error keeps the token it was found at, boxed to keep results small.
+// Display gives the bare message, the line and location are added when the
+// error is reported.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
 pub enum ParseError {
-    #[error("Parse error: [line {0}] Error: {1}")]
-    Error(TokenType, String),
+    #[error("{1}")]
+    UnexpectedToken(Box<Token>, String),
+    #[error("Expect expression.")]
+    ExpectedExpression(Box<Token>),
+    #[error("Invalid assignment target.")]
+    InvalidAssignmentTarget(Box<Token>),
+    #[error("Can't have more than 255 {1}.")]
+    TooManyItems(Box<Token>, &'static str),
 }
 
-// #[allow(dead_code)]
-// #[derive(Clone, Debug)]
-// pub struct ParseError {
-//     pub token: Token,
-//     pub message: String,
-// }
+impl ParseError {
+    pub fn token(&self) -> &Token {
+        match self {
+            ParseError::UnexpectedToken(token, _)
+            | ParseError::ExpectedExpression(token)
+            | ParseError::InvalidAssignmentTarget(token)
+            | ParseError::TooManyItems(token, _) => token,
+        }
+    }
 
-// impl ParseError {
-//     pub fn new(token: Token, message: String) -> Self {
-//         Self { token, message }
-//     }
-// }
+    // where in the line the error is, e.g. " at end" or " at 'foo'"
+    pub fn location(&self) -> String {
+        let token = self.token();
+        match (&token.token_type, &token.lexeme) {
+            (TokenType::Eof, _) => " at end".to_string(),
+            (_, Some(lexeme)) => format!(" at '{}'", lexeme),
+            (token_type, None) => format!(" at '{}'", token_type),
+        }
+    }
+}
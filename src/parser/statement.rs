@@ -1,40 +1,95 @@
+use std::rc::Rc;
+
 use crate::{
-    interpreter::interpreter_error::InterpreterError, token::Token,
+    token::{span::Span, Token},
     visitor::statement_visitor::StatementVisitor,
 };
 
-use super::{expression::Expression, object::Object};
+use super::expression::Expression;
 
+// the leading token of block, if, import, print, return and while statements
+// is kept so every statement can report where it came from
 #[derive(Clone, Debug)]
 pub enum Statement {
-    Block(Vec<Statement>),
+    Block(Token, Vec<Statement>),
+    Constant(Token, Expression),
     Expression(Expression),
-    Function(Token, Vec<Token>, Vec<Statement>),
-    If(Expression, Box<Statement>, Option<Box<Statement>>),
-    Print(Expression),
-    Return(Option<Expression>),
+    // the parameters and body are shared with every function value made from them
+    Function(Token, Rc<[Parameter]>, Rc<[Statement]>),
+    If(Token, Expression, Box<Statement>, Option<Box<Statement>>),
+    // import "path" as alias; or from "path" import names;
+    Import(Token, Token, Option<Token>, Vec<Token>),
+    Print(Token, Expression),
+    Return(Token, Option<Expression>),
     Variable(Token, Option<Expression>),
-    While(Expression, Box<Statement>),
+    While(Token, Expression, Box<Statement>),
+}
+
+// `b = 2` has a default, used when no argument is given for it, and `...rest`
+// collects the arguments after the others into a list
+#[derive(Clone, Debug)]
+pub struct Parameter {
+    pub name: Token,
+    pub default: Option<Expression>,
+    pub rest: bool,
 }
 
 impl Statement {
-    pub fn accept(
+    pub fn accept<T: Default, E, V: StatementVisitor<T, E> + ?Sized>(
         &self,
-        visitor: &mut dyn StatementVisitor,
-    ) -> Result<Option<Object>, InterpreterError> {
+        visitor: &mut V,
+    ) -> Result<T, E> {
         match self {
-            Statement::Block(statements) => visitor.visit_block_statement(statements),
+            Statement::Block(_, statements) => visitor.visit_block_statement(statements),
+            Statement::Constant(name, initializer) => {
+                visitor.visit_constant_statement(name, initializer)
+            }
             Statement::Expression(expr) => visitor.visit_expression_statement(expr),
             Statement::Function(name, params, body) => {
                 visitor.visit_function_statement(name, params, body)
             }
-            Statement::If(condition, then_branch, else_branch) => {
-                visitor.visit_if_statement(condition, then_branch, else_branch)
+            Statement::If(keyword, condition, then_branch, else_branch) => {
+                visitor.visit_if_statement(keyword, condition, then_branch, else_branch)
+            }
+            Statement::Import(_, path, alias, names) => {
+                visitor.visit_import_statement(path, alias, names)
             }
-            Statement::Print(expr) => visitor.visit_print_statement(expr),
-            Statement::Return(expr) => visitor.visit_return_statement(expr),
+            Statement::Print(_, expr) => visitor.visit_print_statement(expr),
+            Statement::Return(_, expr) => visitor.visit_return_statement(expr),
             Statement::Variable(name, expr) => visitor.visit_variable_statement(name, expr),
-            Statement::While(condition, body) => visitor.visit_while_statement(condition, body),
+            Statement::While(keyword, condition, body) => {
+                visitor.visit_while_statement(keyword, condition, body)
+            }
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            Statement::Expression(expr) => expr.line(),
+            Statement::Block(token, _)
+            | Statement::Constant(token, _)
+            | Statement::Function(token, _, _)
+            | Statement::If(token, _, _, _)
+            | Statement::Import(token, _, _, _)
+            | Statement::Print(token, _)
+            | Statement::Return(token, _)
+            | Statement::Variable(token, _)
+            | Statement::While(token, _, _) => token.line,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression(expr) => expr.span(),
+            Statement::Block(token, _)
+            | Statement::Constant(token, _)
+            | Statement::Function(token, _, _)
+            | Statement::If(token, _, _, _)
+            | Statement::Import(token, _, _, _)
+            | Statement::Print(token, _)
+            | Statement::Return(token, _)
+            | Statement::Variable(token, _)
+            | Statement::While(token, _, _) => token.span,
         }
     }
 }
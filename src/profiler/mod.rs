@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    interpreter::{
+        hook::Hook, runtime_error::RuntimeError, script_statements::ScriptStatements, Interpreter,
+    },
+    parser::{object::Object, statement::Statement},
+};
+
+// what was measured for every call of one function
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FunctionProfile {
+    pub calls: u64,
+    // time from call to return, not counted again for recursive calls
+    pub inclusive: Duration,
+    // inclusive time less the time spent in the calls it made
+    pub exclusive: Duration,
+}
+
+// a call that hasn't returned yet
+struct Active {
+    name: String,
+    start: Instant,
+    // time spent in the calls this one made
+    children: Duration,
+}
+
+// Measures where a program spends its time: calls and wall time for every
+// function, how often each line runs, and the exclusive time of every call
+// stack in the folded format flamegraph tools read. Only the lines of the
+// script are counted, not those of the modules it imports.
+pub struct Profiler {
+    functions: HashMap<String, FunctionProfile>,
+    statements: ScriptStatements,
+    lines: BTreeMap<usize, u64>,
+    // "script;<fn a>;<fn b>" to the exclusive time of that stack
+    stacks: HashMap<String, Duration>,
+    // the script itself is the first active call
+    active: Vec<Active>,
+}
+
+impl Profiler {
+    pub fn new(statements: &[Statement]) -> Self {
+        Self {
+            functions: HashMap::new(),
+            statements: ScriptStatements::new(statements),
+            lines: BTreeMap::new(),
+            stacks: HashMap::new(),
+            active: vec![Active {
+                name: "script".to_string(),
+                start: Instant::now(),
+                children: Duration::ZERO,
+            }],
+        }
+    }
+
+    pub fn functions(&self) -> &HashMap<String, FunctionProfile> {
+        &self.functions
+    }
+
+    pub fn lines(&self) -> &BTreeMap<usize, u64> {
+        &self.lines
+    }
+
+    // stops the clock on the script, once the program has finished
+    pub fn finish(&mut self) {
+        while !self.active.is_empty() {
+            self.leave();
+        }
+    }
+
+    // a table of the functions, slowest first, followed by the line counts
+    pub fn write_report(&self, output: &mut dyn Write) -> io::Result<()> {
+        let mut functions: Vec<_> = self.functions().iter().collect();
+        functions.sort_by_key(|(_, profile)| std::cmp::Reverse(profile.exclusive));
+
+        writeln!(
+            output,
+            "{:<24} {:>8} {:>14} {:>14}",
+            "function", "calls", "inclusive ms", "exclusive ms"
+        )?;
+        for (name, profile) in functions {
+            writeln!(
+                output,
+                "{:<24} {:>8} {:>14.3} {:>14.3}",
+                name,
+                profile.calls,
+                profile.inclusive.as_secs_f64() * 1000.0,
+                profile.exclusive.as_secs_f64() * 1000.0
+            )?;
+        }
+
+        writeln!(output)?;
+        writeln!(output, "{:>6} {:>8}", "line", "hits")?;
+        for (line, hits) in self.lines() {
+            writeln!(output, "{:>6} {:>8}", line, hits)?;
+        }
+        Ok(())
+    }
+
+    // one "stack microseconds" line per call stack, sorted by stack
+    pub fn write_folded(&self, output: &mut dyn Write) -> io::Result<()> {
+        let mut stacks: Vec<_> = self.stacks.iter().collect();
+        stacks.sort();
+        for (stack, time) in stacks {
+            writeln!(output, "{} {}", stack, time.as_micros())?;
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        let stack = self
+            .active
+            .iter()
+            .map(|call| call.name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let call = self.active.pop().unwrap();
+        let elapsed = call.start.elapsed();
+        let exclusive = elapsed.saturating_sub(call.children);
+
+        let recursive = self.active.iter().any(|outer| outer.name == call.name);
+        let profile = self.functions.entry(call.name).or_default();
+        profile.calls += 1;
+        profile.exclusive += exclusive;
+        if !recursive {
+            profile.inclusive += elapsed;
+        }
+
+        *self.stacks.entry(stack).or_default() += exclusive;
+        if let Some(caller) = self.active.last_mut() {
+            caller.children += elapsed;
+        }
+    }
+}
+
+impl Hook for Profiler {
+    fn before_statement(
+        &mut self,
+        _interpreter: &Interpreter,
+        statement: &Statement,
+    ) -> Result<(), RuntimeError> {
+        if let Some(line) = self.statements.line(statement) {
+            *self.lines.entry(line).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn on_call(&mut self, interpreter: &Interpreter) {
+        let frame = interpreter.frames().last().unwrap();
+        self.active.push(Active {
+            name: frame.name.clone(),
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn on_return(&mut self, _interpreter: &Interpreter, _value: Option<&Object>) {
+        self.leave();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser};
+    use std::{cell::RefCell, fs, rc::Rc};
+
+    #[test]
+    fn test_profile() {
+        let source = "fun fib(n) {\n  if (n < 2) return n;\n  return fib(n - 1) + fib(n - 2);\n}\nprint fib(5);";
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        let profiler = Rc::new(RefCell::new(Profiler::new(&statements)));
+        Interpreter::new()
+            .with_hook(Box::new(Rc::clone(&profiler)))
+            .interpret(&statements)
+            .unwrap();
+        let mut profiler = profiler.borrow_mut();
+        profiler.finish();
+
+        let fib = profiler.functions()["<fn fib>"];
+        assert_eq!(fib.calls, 15);
+        assert!(fib.exclusive <= fib.inclusive);
+        assert_eq!(profiler.functions()["script"].calls, 1);
+
+        let lines: Vec<_> = profiler
+            .lines()
+            .iter()
+            .map(|(&line, &hits)| (line, hits))
+            .collect();
+        // line 2 counts the if and, for the 8 calls that stop there, its return
+        assert_eq!(lines, vec![(1, 1), (2, 23), (3, 7), (5, 1)]);
+
+        let mut folded = Vec::new();
+        profiler.write_folded(&mut folded).unwrap();
+        let folded = String::from_utf8(folded).unwrap();
+        let stacks: Vec<_> = folded
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0)
+            .collect();
+        assert_eq!(stacks.len(), 6);
+        assert_eq!(stacks[0], "script");
+        assert_eq!(
+            stacks[5],
+            "script;<fn fib>;<fn fib>;<fn fib>;<fn fib>;<fn fib>"
+        );
+    }
+
+    #[test]
+    fn test_module_lines_are_not_counted() {
+        let directory = std::env::temp_dir().join(format!("lox-profile-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("m.lox"),
+            "var y = 1;\nfun g() {\n  return 2;\n}",
+        )
+        .unwrap();
+
+        let source = "import \"m.lox\" as m;\nvar x = m.g();";
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        let profiler = Rc::new(RefCell::new(Profiler::new(&statements)));
+        Interpreter::new()
+            .with_script(&directory.join("main.lox"))
+            .with_hook(Box::new(Rc::clone(&profiler)))
+            .interpret(&statements)
+            .unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        let profiler = profiler.borrow();
+        let lines: Vec<_> = profiler
+            .lines()
+            .iter()
+            .map(|(&line, &hits)| (line, hits))
+            .collect();
+        assert_eq!(lines, vec![(1, 1), (2, 1)]);
+        assert_eq!(profiler.functions()["<fn g>"].calls, 1);
+    }
+}
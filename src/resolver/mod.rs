@@ -0,0 +1,303 @@
+pub mod resolve_error;
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use resolve_error::ResolveError;
+
+use crate::{
+    error_bag::ErrorBag,
+    parser::{
+        expression::Expression,
+        statement::{Parameter, Statement},
+    },
+    token::Token,
+    visitor::{expression_visitor::ExpressionVisitor, statement_visitor::StatementVisitor},
+};
+
+// A static pass over the whole program before it runs. It follows the scopes
+// the interpreter will create and reports the errors that can be found
+// without running anything. Names it can't see yet, like a global declared
+// after the function that uses it, are left to the runtime checks.
+pub struct Resolver<'a> {
+    errors: &'a mut ErrorBag,
+    // innermost scope last, each maps a declared name to whether it is a constant
+    scopes: Vec<HashMap<String, bool>>,
+    natives: HashSet<String>,
+    strict: bool,
+    // how many function bodies the current statement is nested in
+    functions: usize,
+    // names used in function bodies that weren't declared yet, they may still
+    // be declared as globals later in the program
+    unresolved: Vec<Token>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(errors: &'a mut ErrorBag) -> Self {
+        Self {
+            errors,
+            scopes: vec![HashMap::new()],
+            natives: HashSet::new(),
+            strict: false,
+            functions: 0,
+            unresolved: Vec::new(),
+        }
+    }
+
+    pub fn with_natives(mut self, natives: impl IntoIterator<Item = String>) -> Self {
+        self.natives = natives.into_iter().collect();
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn resolve(&mut self, statements: &[Statement]) {
+        self.resolve_statements(statements);
+
+        // every global is known by now
+        for name in std::mem::take(&mut self.unresolved) {
+            let key = name.value.to_string();
+            if !self.scopes[0].contains_key(&key) && !self.natives.contains(&key) {
+                self.error(ResolveError::UndefinedVariable(Box::new(name)));
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &Token, constant: bool) {
+        let global = self.scopes.len() == 1;
+        let scope = self.scopes.last_mut().unwrap();
+        let key = name.value.to_string();
+        let error = match scope.get(&key) {
+            Some(true) => Some(ResolveError::ConstantRedeclaration(Box::new(name.clone()))),
+            Some(false) if self.strict && global => {
+                Some(ResolveError::GlobalRedeclaration(Box::new(name.clone())))
+            }
+            _ if self.strict && self.natives.contains(&key) => {
+                Some(ResolveError::NativeShadowing(Box::new(name.clone())))
+            }
+            _ => None,
+        };
+
+        scope.insert(key, constant);
+        if let Some(error) = error {
+            self.error(error);
+        }
+    }
+
+    fn error(&mut self, error: ResolveError) {
+        self.errors.report(error.into());
+    }
+
+    fn lookup(&self, name: &Token) -> Option<bool> {
+        let key = name.value.to_string();
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&key).copied())
+    }
+
+    fn resolve_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let _ = statement.accept(self);
+        }
+    }
+
+    // checks a name that is read or assigned, returning whether it is a constant
+    fn resolve_name(&mut self, name: &Token) -> bool {
+        match self.lookup(name) {
+            Some(constant) => constant,
+            None if !self.strict || self.natives.contains(&name.value.to_string()) => false,
+            // top level code runs in order, so the global has to be declared already
+            None if self.functions == 0 => {
+                self.error(ResolveError::UndefinedVariable(Box::new(name.clone())));
+                false
+            }
+            None => {
+                self.unresolved.push(name.clone());
+                false
+            }
+        }
+    }
+
+    fn scoped(&mut self, statements: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        self.resolve_statements(statements);
+        self.scopes.pop();
+    }
+}
+
+impl StatementVisitor<(), ()> for Resolver<'_> {
+    fn visit_expression(&mut self, expression: &Expression) -> Result<(), ()> {
+        expression.accept(self)
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Statement]) -> Result<(), ()> {
+        self.scoped(statements);
+        Ok(())
+    }
+
+    fn visit_constant_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Expression,
+    ) -> Result<(), ()> {
+        self.visit_expression(initializer)?;
+        self.declare(name, true);
+        Ok(())
+    }
+
+    fn visit_function_statement(
+        &mut self,
+        name: &Token,
+        params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<(), ()> {
+        self.declare(name, false);
+
+        self.functions += 1;
+        self.scopes.push(HashMap::new());
+        // a default can use the parameters before it
+        for param in params.iter() {
+            if let Some(default) = &param.default {
+                default.accept(self)?;
+            }
+            self.declare(&param.name, false);
+        }
+        self.resolve_statements(body);
+        self.scopes.pop();
+        self.functions -= 1;
+
+        Ok(())
+    }
+
+    fn visit_import_statement(
+        &mut self,
+        _path: &Token,
+        alias: &Option<Token>,
+        names: &[Token],
+    ) -> Result<(), ()> {
+        for name in alias.iter().chain(names) {
+            self.declare(name, false);
+        }
+        Ok(())
+    }
+
+    fn visit_variable_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expression>,
+    ) -> Result<(), ()> {
+        if let Some(initializer) = initializer {
+            self.visit_expression(initializer)?;
+        }
+        self.declare(name, false);
+        Ok(())
+    }
+}
+
+impl ExpressionVisitor<(), ()> for Resolver<'_> {
+    fn visit_assignment(&mut self, name: &Token, expression: &Expression) -> Result<(), ()> {
+        expression.accept(self)?;
+        if self.resolve_name(name) {
+            self.error(ResolveError::ConstantAssignment(Box::new(name.clone())));
+        }
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, name: &Token) -> Result<(), ()> {
+        self.resolve_name(name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::LoxError, lexer::Lexer, parser::Parser};
+    use rstest::*;
+
+    fn resolve(source: &str) -> Vec<LoxError> {
+        resolve_with(source, false)
+    }
+
+    fn resolve_with(source: &str, strict: bool) -> Vec<LoxError> {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+        Resolver::new(&mut errors)
+            .with_natives(["clock".to_string()])
+            .with_strict(strict)
+            .resolve(&statements);
+        errors.errors().to_vec()
+    }
+
+    #[rstest]
+    #[case("const a = 1; a = 2;")]
+    #[case("const a = 1; a += 2;")]
+    #[case("const a = 1; { a = 2; }")]
+    #[case("const a = 1; fun f() { a = 2; }")]
+    #[case("fun f() { const a = 1; while (true) a = 2; }")]
+    fn test_constant_assignment(#[case] source: &str) {
+        assert!(matches!(
+            resolve(source).as_slice(),
+            [LoxError::Resolution(ResolveError::ConstantAssignment(_))]
+        ));
+    }
+
+    #[rstest]
+    #[case("const a = 1; var a = 2;")]
+    #[case("const a = 1; const a = 2;")]
+    #[case("const a = 1; fun a() {}")]
+    fn test_constant_redeclaration(#[case] source: &str) {
+        assert!(matches!(
+            resolve(source).as_slice(),
+            [LoxError::Resolution(ResolveError::ConstantRedeclaration(_))]
+        ));
+    }
+
+    #[rstest]
+    #[case("var a = 1; a = 2;")]
+    #[case("const a = 1; { var a = 2; a = 3; }")]
+    #[case("const a = 1; fun f(a) { a = 2; }")]
+    #[case("var a = 1; const a = 2;")]
+    // only known once the program runs
+    #[case("fun f() { a = 2; } const a = 1;")]
+    fn test_allowed(#[case] source: &str) {
+        assert_eq!(resolve(source), vec![]);
+    }
+
+    #[rstest]
+    #[case("var a = 1; var a = 2;", "E0403")]
+    #[case("var a = 1; fun a() {}", "E0403")]
+    #[case("var clock = 1;", "E0404")]
+    #[case("fun f(clock) {}", "E0404")]
+    #[case("{ var clock = 1; }", "E0404")]
+    #[case("print a;", "E0405")]
+    #[case("a = 1;", "E0405")]
+    #[case("{ print a; } var a = 1;", "E0405")]
+    #[case("var a = a;", "E0405")]
+    #[case("fun f() { return b; }", "E0405")]
+    fn test_strict_errors(#[case] source: &str, #[case] code: &str) {
+        let errors = resolve_with(source, true);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), code);
+        assert_eq!(resolve(source), vec![]);
+    }
+
+    #[rstest]
+    #[case("var a = 1; { var a = 2; }")]
+    #[case("print clock();")]
+    #[case("fun f() { return a; } var a = 1;")]
+    #[case("fun f() { a = 2; } var a = 1;")]
+    #[case("fun f(n) { return n > 0 ? f(n - 1) : 0; }")]
+    #[case("fun f(a, b = a) { return b; }")]
+    fn test_strict_allowed(#[case] source: &str) {
+        assert_eq!(resolve_with(source, true), vec![]);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::token::Token;
+
+// Like parse errors, each keeps the token it is reported at and Display gives
+// the bare message.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    #[error("Can't assign to constant '{}'.", .0.value)]
+    ConstantAssignment(Box<Token>),
+    #[error("Can't redeclare constant '{}'.", .0.value)]
+    ConstantRedeclaration(Box<Token>),
+    // the rest are only reported in strict mode
+    #[error("Global '{}' is already declared.", .0.value)]
+    GlobalRedeclaration(Box<Token>),
+    #[error("Can't shadow native function '{}'.", .0.value)]
+    NativeShadowing(Box<Token>),
+    #[error("Undefined variable '{}'.", .0.value)]
+    UndefinedVariable(Box<Token>),
+}
+
+impl ResolveError {
+    pub fn token(&self) -> &Token {
+        match self {
+            ResolveError::ConstantAssignment(token)
+            | ResolveError::ConstantRedeclaration(token)
+            | ResolveError::GlobalRedeclaration(token)
+            | ResolveError::NativeShadowing(token)
+            | ResolveError::UndefinedVariable(token) => token,
+        }
+    }
+}
@@ -1,9 +1,12 @@
+use serde_json::{json, Value};
 use std::fmt::Display;
 use std::io::Write;
 
+use span::Span;
 use token_type::TokenType;
 use token_value::TokenValue;
 
+pub mod span;
 pub mod token_type;
 pub mod token_value;
 
@@ -13,6 +16,7 @@ pub struct Token {
     pub line: usize,
     pub lexeme: Option<String>,
     pub value: TokenValue,
+    pub span: Span,
 }
 
 impl Token {
@@ -27,9 +31,15 @@ impl Token {
             line,
             lexeme,
             value,
+            span: Span::default(),
         }
     }
 
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
     pub fn from_token_type(line: usize, token_type: TokenType) -> Self {
         Self::new(token_type, line, None, TokenValue::None)
     }
@@ -43,10 +53,28 @@ impl Token {
         )
     }
 
+    pub fn new_integer(line: usize, lexeme: String, value: i64) -> Self {
+        Self::new(
+            TokenType::Number,
+            line,
+            Some(lexeme),
+            TokenValue::Integer(value),
+        )
+    }
+
     pub fn new_string(line: usize, value: String) -> Self {
         Self::new(TokenType::String, line, None, TokenValue::String(value))
     }
 
+    pub fn new_interpolation(line: usize, value: String) -> Self {
+        Self::new(
+            TokenType::Interpolation,
+            line,
+            None,
+            TokenValue::String(value),
+        )
+    }
+
     pub fn new_identifier(line: usize, value: String) -> Self {
         Self::new(
             TokenType::Identifier,
@@ -68,14 +96,33 @@ impl Token {
             TokenType::LeftBrace => writeln!(output, "LEFT_BRACE {{ null"),
             TokenType::RightBrace => writeln!(output, "RIGHT_BRACE }} null"),
             // separator tokens
+            TokenType::Colon => writeln!(output, "COLON : null"),
             TokenType::Comma => writeln!(output, "COMMA , null"),
             TokenType::Dot => writeln!(output, "DOT . null"),
+            TokenType::DotDotDot => writeln!(output, "DOT_DOT_DOT ... null"),
             TokenType::Semicolon => writeln!(output, "SEMICOLON ; null"),
+            // conditional tokens
+            TokenType::Question => writeln!(output, "QUESTION ? null"),
+            TokenType::QuestionQuestion => writeln!(output, "QUESTION_QUESTION ?? null"),
             // arithmetic tokens
             TokenType::Minus => writeln!(output, "MINUS - null"),
             TokenType::Plus => writeln!(output, "PLUS + null"),
             TokenType::Star => writeln!(output, "STAR * null"),
             TokenType::Slash => writeln!(output, "SLASH / null"),
+            TokenType::Percent => writeln!(output, "PERCENT % null"),
+            TokenType::TildeSlash => writeln!(output, "TILDE_SLASH ~/ null"),
+            TokenType::MinusEqual => writeln!(output, "MINUS_EQUAL -= null"),
+            TokenType::PlusEqual => writeln!(output, "PLUS_EQUAL += null"),
+            TokenType::SlashEqual => writeln!(output, "SLASH_EQUAL /= null"),
+            TokenType::StarEqual => writeln!(output, "STAR_EQUAL *= null"),
+            TokenType::StarStar => writeln!(output, "STAR_STAR ** null"),
+            // bitwise tokens
+            TokenType::Ampersand => writeln!(output, "AMPERSAND & null"),
+            TokenType::Caret => writeln!(output, "CARET ^ null"),
+            TokenType::Pipe => writeln!(output, "PIPE | null"),
+            TokenType::Tilde => writeln!(output, "TILDE ~ null"),
+            TokenType::LessLess => writeln!(output, "LESS_LESS << null"),
+            TokenType::GreaterGreater => writeln!(output, "GREATER_GREATER >> null"),
             // comparison tokens
             TokenType::Equal => writeln!(output, "EQUAL = null"),
             TokenType::EqualEqual => writeln!(output, "EQUAL_EQUAL == null"),
@@ -87,12 +134,16 @@ impl Token {
             TokenType::LessEqual => writeln!(output, "LESS_EQUAL <= null"),
             // keywords
             TokenType::And => writeln!(output, "AND and null"),
+            TokenType::As => writeln!(output, "AS as null"),
             TokenType::Class => writeln!(output, "CLASS class null"),
+            TokenType::Const => writeln!(output, "CONST const null"),
             TokenType::Else => writeln!(output, "ELSE else null"),
             TokenType::False => writeln!(output, "FALSE false null"),
             TokenType::For => writeln!(output, "FOR for null"),
             TokenType::Fun => writeln!(output, "FUN fun null"),
+            TokenType::From => writeln!(output, "FROM from null"),
             TokenType::If => writeln!(output, "IF if null"),
+            TokenType::Import => writeln!(output, "IMPORT import null"),
             TokenType::Nil => writeln!(output, "NIL nil null"),
             TokenType::Or => writeln!(output, "OR or null"),
             TokenType::Print => writeln!(output, "PRINT print null"),
@@ -102,6 +153,13 @@ impl Token {
             TokenType::True => writeln!(output, "TRUE true null"),
             TokenType::Var => writeln!(output, "VAR var null"),
             TokenType::While => writeln!(output, "WHILE while null"),
+            // trivia tokens
+            TokenType::Comment | TokenType::Whitespace => writeln!(
+                output,
+                "{} {:?} null",
+                self.token_type.name(),
+                self.lexeme.as_deref().unwrap_or_default()
+            ),
             // special tokens
             TokenType::Eof => writeln!(output, "EOF  null"),
             // literals
@@ -112,35 +170,71 @@ impl Token {
                 }
                 unreachable!("Expected identifier.  Found {:?}", self.value);
             }
-            TokenType::String => {
+            TokenType::String | TokenType::Interpolation => {
                 if let TokenValue::String(value) = &self.value {
-                    writeln!(output, "STRING \"{0}\" {0}", value)?;
+                    match &self.lexeme {
+                        Some(lexeme) => {
+                            writeln!(output, "{} {} {}", self.token_type.name(), lexeme, value)?
+                        }
+                        None => writeln!(output, "{} \"{1}\" {1}", self.token_type.name(), value)?,
+                    }
                     return Ok(());
                 }
                 unreachable!("Expected string.  Found {:?}", self.value);
             }
             TokenType::Number => {
-                // this is a hack to get the output to match the book
-                if let TokenValue::Number(value) = self.value {
-                    if let Some(lexeme) = &self.lexeme {
-                        if f64::trunc(value) == value {
+                // the book prints every number literal as a double
+                if let Some(lexeme) = &self.lexeme {
+                    match self.value {
+                        TokenValue::Integer(value) => {
+                            writeln!(output, "NUMBER {} {:.1}", lexeme, value as f64)?;
+                            return Ok(());
+                        }
+                        TokenValue::Number(value) if f64::trunc(value) == value => {
                             writeln!(output, "NUMBER {} {:.1}", lexeme, value)?;
-                        } else {
+                            return Ok(());
+                        }
+                        TokenValue::Number(value) => {
                             writeln!(output, "NUMBER {} {}", lexeme, value)?;
+                            return Ok(());
                         }
-                        return Ok(());
+                        _ => {}
                     }
                 }
                 unreachable!("Expected lexeme.  Found {:?}", self.lexeme);
             }
         }
     }
+
+    pub fn to_json(&self) -> Value {
+        let literal = match &self.value {
+            TokenValue::Integer(value) => json!(value),
+            TokenValue::Number(value) => json!(value),
+            TokenValue::String(value) => json!(value),
+            TokenValue::None | TokenValue::Identifier(_) => Value::Null,
+        };
+
+        json!({
+            "type": self.token_type.name(),
+            "lexeme": self.lexeme,
+            "literal": literal,
+            "span": {
+                "start": self.span.start,
+                "end": self.span.end,
+                "line": self.span.line,
+                "column": self.span.column,
+                "end_line": self.span.end_line,
+                "end_column": self.span.end_column,
+            },
+        })
+    }
 }
 
 impl Display for TokenValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TokenValue::None => write!(f, ""),
+            TokenValue::Integer(value) => write!(f, "{}", value),
             TokenValue::Number(value) => {
                 if f64::trunc(*value) == *value {
                     write!(f, "{:.1}", value)
@@ -191,6 +285,17 @@ mod tests {
         assert_eq!(token.value, TokenValue::Number(1.23));
     }
 
+    #[test]
+    fn test_token_new_integer() {
+        let token = Token::new_integer(2, "0xFF".to_string(), 255);
+        assert_eq!(token.token_type, TokenType::Number);
+        assert_eq!(token.value, TokenValue::Integer(255));
+
+        let mut output = Vec::new();
+        token.print(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "NUMBER 0xFF 255.0\n");
+    }
+
     #[test]
     fn test_token_new_string() {
         let token = Token::new_string(2, "test_string".to_string());
@@ -228,6 +333,7 @@ mod tests {
     #[case(TokenType::RightBrace, "RIGHT_BRACE } null\n")]
     #[case(TokenType::Comma, "COMMA , null\n")]
     #[case(TokenType::Dot, "DOT . null\n")]
+    #[case(TokenType::DotDotDot, "DOT_DOT_DOT ... null\n")]
     #[case(TokenType::Semicolon, "SEMICOLON ; null\n")]
     #[case(TokenType::Minus, "MINUS - null\n")]
     #[case(TokenType::Plus, "PLUS + null\n")]
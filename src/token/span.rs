@@ -0,0 +1,30 @@
+// Where a token sits in the source. Offsets are half-open byte offsets,
+// lines and columns are 1-based with columns counted in characters, and
+// the end position points just past the last character.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    pub fn new(
+        start: usize,
+        end: usize,
+        (line, column): (usize, usize),
+        (end_line, end_column): (usize, usize),
+    ) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+}
@@ -9,15 +9,39 @@ pub enum TokenType {
     RightBrace,
 
     // separator tokens
+    Colon,
     Comma,
     Dot,
+    DotDotDot,
     Semicolon,
 
+    // conditional tokens
+    Question,
+    QuestionQuestion,
+
     // arithmetic tokens
     Minus,
+    Percent,
     Plus,
     Slash,
     Star,
+    // integer division
+    TildeSlash,
+    StarStar,
+
+    // compound assignment tokens
+    MinusEqual,
+    PlusEqual,
+    SlashEqual,
+    StarEqual,
+
+    // bitwise tokens
+    Ampersand,
+    Caret,
+    Pipe,
+    Tilde,
+    LessLess,
+    GreaterGreater,
 
     // comparison tokens
     Bang,
@@ -32,16 +56,22 @@ pub enum TokenType {
     // literals
     Identifier,
     String,
+    // the part of a string before an interpolated expression
+    Interpolation,
     Number,
 
     // keywords
     And,
+    As,
     Class,
+    Const,
     Else,
     False,
     Fun,
     For,
+    From,
     If,
+    Import,
     Nil,
     Or,
     Print,
@@ -52,11 +82,83 @@ pub enum TokenType {
     Var,
     While,
 
+    // trivia tokens, only produced when the lexer is asked to keep them
+    Comment,
+    Whitespace,
+
     // special tokens
     Eof,
 }
 
 impl TokenType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::LeftParen => "LEFT_PAREN",
+            TokenType::RightParen => "RIGHT_PAREN",
+            TokenType::LeftBrace => "LEFT_BRACE",
+            TokenType::RightBrace => "RIGHT_BRACE",
+            TokenType::Colon => "COLON",
+            TokenType::Comma => "COMMA",
+            TokenType::Dot => "DOT",
+            TokenType::DotDotDot => "DOT_DOT_DOT",
+            TokenType::Semicolon => "SEMICOLON",
+            TokenType::Question => "QUESTION",
+            TokenType::QuestionQuestion => "QUESTION_QUESTION",
+            TokenType::Minus => "MINUS",
+            TokenType::Percent => "PERCENT",
+            TokenType::Plus => "PLUS",
+            TokenType::Slash => "SLASH",
+            TokenType::Star => "STAR",
+            TokenType::TildeSlash => "TILDE_SLASH",
+            TokenType::MinusEqual => "MINUS_EQUAL",
+            TokenType::PlusEqual => "PLUS_EQUAL",
+            TokenType::SlashEqual => "SLASH_EQUAL",
+            TokenType::StarEqual => "STAR_EQUAL",
+            TokenType::StarStar => "STAR_STAR",
+            TokenType::Ampersand => "AMPERSAND",
+            TokenType::Caret => "CARET",
+            TokenType::Pipe => "PIPE",
+            TokenType::Tilde => "TILDE",
+            TokenType::LessLess => "LESS_LESS",
+            TokenType::GreaterGreater => "GREATER_GREATER",
+            TokenType::Bang => "BANG",
+            TokenType::BangEqual => "BANG_EQUAL",
+            TokenType::Equal => "EQUAL",
+            TokenType::EqualEqual => "EQUAL_EQUAL",
+            TokenType::Greater => "GREATER",
+            TokenType::GreaterEqual => "GREATER_EQUAL",
+            TokenType::Less => "LESS",
+            TokenType::LessEqual => "LESS_EQUAL",
+            TokenType::Identifier => "IDENTIFIER",
+            TokenType::String => "STRING",
+            TokenType::Interpolation => "INTERPOLATION",
+            TokenType::Number => "NUMBER",
+            TokenType::And => "AND",
+            TokenType::As => "AS",
+            TokenType::Class => "CLASS",
+            TokenType::Const => "CONST",
+            TokenType::Else => "ELSE",
+            TokenType::False => "FALSE",
+            TokenType::Fun => "FUN",
+            TokenType::For => "FOR",
+            TokenType::From => "FROM",
+            TokenType::If => "IF",
+            TokenType::Import => "IMPORT",
+            TokenType::Nil => "NIL",
+            TokenType::Or => "OR",
+            TokenType::Print => "PRINT",
+            TokenType::Return => "RETURN",
+            TokenType::Super => "SUPER",
+            TokenType::This => "THIS",
+            TokenType::True => "TRUE",
+            TokenType::Var => "VAR",
+            TokenType::While => "WHILE",
+            TokenType::Comment => "COMMENT",
+            TokenType::Whitespace => "WHITESPACE",
+            TokenType::Eof => "EOF",
+        }
+    }
+
     // pub fn is_keyword(&self) -> bool {
     //     match self {
     //         TokenType::And | TokenType::Class | TokenType::Else | TokenType::False |
@@ -75,13 +177,30 @@ impl Display for TokenType {
             TokenType::RightParen => write!(f, ")"),
             TokenType::LeftBrace => write!(f, "{{"),
             TokenType::RightBrace => write!(f, "}}"),
+            TokenType::Colon => write!(f, ":"),
             TokenType::Comma => write!(f, ","),
             TokenType::Dot => write!(f, "."),
+            TokenType::DotDotDot => write!(f, "..."),
             TokenType::Semicolon => write!(f, ";"),
+            TokenType::Question => write!(f, "?"),
+            TokenType::QuestionQuestion => write!(f, "??"),
             TokenType::Minus => write!(f, "-"),
+            TokenType::Percent => write!(f, "%"),
             TokenType::Plus => write!(f, "+"),
             TokenType::Slash => write!(f, "/"),
             TokenType::Star => write!(f, "*"),
+            TokenType::TildeSlash => write!(f, "~/"),
+            TokenType::MinusEqual => write!(f, "-="),
+            TokenType::PlusEqual => write!(f, "+="),
+            TokenType::SlashEqual => write!(f, "/="),
+            TokenType::StarEqual => write!(f, "*="),
+            TokenType::StarStar => write!(f, "**"),
+            TokenType::Ampersand => write!(f, "&"),
+            TokenType::Caret => write!(f, "^"),
+            TokenType::Pipe => write!(f, "|"),
+            TokenType::Tilde => write!(f, "~"),
+            TokenType::LessLess => write!(f, "<<"),
+            TokenType::GreaterGreater => write!(f, ">>"),
             TokenType::Bang => write!(f, "!"),
             TokenType::BangEqual => write!(f, "!="),
             TokenType::Equal => write!(f, "="),
@@ -92,9 +211,31 @@ impl Display for TokenType {
             TokenType::LessEqual => write!(f, "<="),
             TokenType::Identifier => write!(f, "IDENTIFIER"),
             TokenType::String => write!(f, "STRING"),
+            TokenType::Interpolation => write!(f, "INTERPOLATION"),
             TokenType::Number => write!(f, "NUMBER"),
+            TokenType::And => write!(f, "and"),
+            TokenType::As => write!(f, "as"),
+            TokenType::Class => write!(f, "class"),
+            TokenType::Const => write!(f, "const"),
+            TokenType::Else => write!(f, "else"),
+            TokenType::False => write!(f, "false"),
+            TokenType::Fun => write!(f, "fun"),
+            TokenType::For => write!(f, "for"),
+            TokenType::From => write!(f, "from"),
+            TokenType::If => write!(f, "if"),
+            TokenType::Import => write!(f, "import"),
+            TokenType::Nil => write!(f, "nil"),
+            TokenType::Or => write!(f, "or"),
+            TokenType::Print => write!(f, "print"),
+            TokenType::Return => write!(f, "return"),
+            TokenType::Super => write!(f, "super"),
+            TokenType::This => write!(f, "this"),
+            TokenType::True => write!(f, "true"),
+            TokenType::Var => write!(f, "var"),
+            TokenType::While => write!(f, "while"),
+            TokenType::Comment => write!(f, "COMMENT"),
+            TokenType::Whitespace => write!(f, "WHITESPACE"),
             TokenType::Eof => write!(f, "EOF"),
-            _ => write!(f, "KEYWORD"),
         }
     }
 }
@@ -2,6 +2,7 @@
 pub enum TokenValue {
     #[default]
     None,
+    Integer(i64),
     Number(f64),
     String(String),
     Identifier(String),
@@ -0,0 +1,133 @@
+use std::io::Write;
+
+use crate::{
+    interpreter::{
+        hook::Hook, runtime_error::RuntimeError, script_statements::ScriptStatements, Interpreter,
+    },
+    parser::{object::Object, statement::Statement},
+};
+
+// Logs every statement the program runs and every call and return, indented
+// by how deep in calls it is. The log goes to its own output so it doesn't
+// mix with what the program prints. Statements of imported modules aren't
+// logged, since their lines aren't lines of the source.
+pub struct Tracer {
+    lines: Vec<String>,
+    statements: ScriptStatements,
+    output: Box<dyn Write>,
+}
+
+impl Tracer {
+    pub fn new(source: &str, statements: &[Statement], output: Box<dyn Write>) -> Self {
+        Self {
+            lines: source.lines().map(str::to_string).collect(),
+            statements: ScriptStatements::new(statements),
+            output,
+        }
+    }
+
+    // tracing is best effort, a failed write shouldn't stop the program
+    fn log(&mut self, depth: usize, message: String) {
+        let _ = writeln!(self.output, "{:indent$}{}", "", message, indent = depth * 2);
+    }
+}
+
+impl Hook for Tracer {
+    fn before_statement(
+        &mut self,
+        interpreter: &Interpreter,
+        statement: &Statement,
+    ) -> Result<(), RuntimeError> {
+        let Some(line) = self.statements.line(statement) else {
+            return Ok(());
+        };
+        let text = self
+            .lines
+            .get(line.wrapping_sub(1))
+            .map_or("", |text| text.trim());
+        let message = format!("line {}: {}", line, text);
+        self.log(interpreter.frames().len() - 1, message);
+        Ok(())
+    }
+
+    fn on_call(&mut self, interpreter: &Interpreter) {
+        let frames = interpreter.frames();
+        let caller = &frames[frames.len() - 2];
+        let message = format!(
+            "call {} from line {} (depth {})",
+            frames[frames.len() - 1].name,
+            caller.line,
+            frames.len() - 1
+        );
+        self.log(frames.len() - 2, message);
+    }
+
+    fn on_return(&mut self, interpreter: &Interpreter, value: Option<&Object>) {
+        let frames = interpreter.frames();
+        let name = &frames[frames.len() - 1].name;
+        let message = match value {
+            Some(value) => format!("return {} -> {} (depth {})", name, value, frames.len() - 1),
+            None => format!("unwind {} (depth {})", name, frames.len() - 1),
+        };
+        self.log(frames.len() - 2, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser, test_output::Output};
+    use std::{fs, path::Path};
+
+    fn trace(directory: &Path, source: &str) -> String {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        let output = Output::default();
+        let tracer = Tracer::new(source, &statements, Box::new(output.clone()));
+        Interpreter::new()
+            .with_script(&directory.join("main.lox"))
+            .with_hook(Box::new(tracer))
+            .interpret(&statements)
+            .unwrap();
+        output.contents()
+    }
+
+    #[test]
+    fn test_trace() {
+        let source = "fun f(a) {\n  return a + 1;\n}\nvar x = f(1);";
+        assert_eq!(
+            trace(Path::new("."), source),
+            "line 1: fun f(a) {\n\
+             line 4: var x = f(1);\n\
+             call <fn f> from line 4 (depth 1)\n  \
+               line 2: return a + 1;\n\
+             return <fn f> -> 2 (depth 1)\n"
+        );
+    }
+
+    #[test]
+    fn test_modules_are_not_traced() {
+        let directory = std::env::temp_dir().join(format!("lox-trace-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("m.lox"),
+            "var y = 1;\nfun g() {\n  return 2;\n}",
+        )
+        .unwrap();
+
+        let source = "import \"m.lox\" as m;\nvar x = m.g();";
+        assert_eq!(
+            trace(&directory, source),
+            "line 1: import \"m.lox\" as m;\n\
+             call <module m.lox> from line 1 (depth 1)\n\
+             return <module m.lox> -> nil (depth 1)\n\
+             line 2: var x = m.g();\n\
+             call <fn g> from line 2 (depth 1)\n\
+             return <fn g> -> 2 (depth 1)\n"
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,4 +1,7 @@
-use crate::parser::{expression::Expression, object::Object};
+use crate::{
+    parser::{expression::Expression, object::Object},
+    token::Token,
+};
 
 use super::expression_visitor::ExpressionVisitor;
 
@@ -34,13 +37,40 @@ impl ExpressionVisitor<String, String> for ExpressionPrinter {
     fn visit_call(
         &mut self,
         callee: &Expression,
+        _paren: &Token,
         arguments: &[Expression],
+        named: &[(Token, Expression)],
     ) -> Result<String, String> {
-        let mut args = String::new();
+        let mut args = Vec::new();
         for arg in arguments {
-            args.push_str(&arg.accept(self)?);
+            args.push(arg.accept(self)?);
         }
-        Ok(format!("(call {} {})", callee.accept(self)?, args))
+        for (name, arg) in named {
+            args.push(format!("(: {} {})", name.value, arg.accept(self)?));
+        }
+        Ok(format!(
+            "(call {} {})",
+            callee.accept(self)?,
+            args.join(" ")
+        ))
+    }
+
+    fn visit_conditional(
+        &mut self,
+        condition: &Expression,
+        then_branch: &Expression,
+        else_branch: &Expression,
+    ) -> Result<String, String> {
+        Ok(format!(
+            "(?: {} {} {})",
+            condition.accept(self)?,
+            then_branch.accept(self)?,
+            else_branch.accept(self)?
+        ))
+    }
+
+    fn visit_get(&mut self, object: &Expression, name: &Token) -> Result<String, String> {
+        Ok(format!("(. {} {})", object.accept(self)?, name.value))
     }
 
     fn visit_grouping(&mut self, expression: &Expression) -> Result<String, String> {
@@ -48,6 +78,21 @@ impl ExpressionVisitor<String, String> for ExpressionPrinter {
         Ok(format!("(group {})", expr))
     }
 
+    fn visit_interpolation(&mut self, parts: &[Expression]) -> Result<String, String> {
+        let mut printed = Vec::new();
+        for part in parts {
+            // quote the string parts so their spacing shows
+            printed.push(match part {
+                Expression::Literal {
+                    value: Object::String(string),
+                    ..
+                } => format!("{:?}", string),
+                _ => part.accept(self)?,
+            });
+        }
+        Ok(format!("(interpolate {})", printed.join(" ")))
+    }
+
     fn visit_literal(&mut self, value: &Object) -> Result<String, String> {
         Ok(format!("{}", value))
     }
@@ -3,23 +3,92 @@ use crate::{
     token::Token,
 };
 
-pub trait ExpressionVisitor<T, E> {
-    fn visit_assignment(&mut self, name: &Token, expression: &Expression) -> Result<T, E>;
+// The default implementations visit every sub-expression and return
+// `T::default()`, so passes only need to override the expressions they care about.
+pub trait ExpressionVisitor<T: Default, E> {
+    fn visit_assignment(&mut self, _name: &Token, expression: &Expression) -> Result<T, E> {
+        expression.accept(self)?;
+        Ok(T::default())
+    }
+
     fn visit_binary(
         &mut self,
         left: &Expression,
-        operator: &Token,
+        _operator: &Token,
         right: &Expression,
-    ) -> Result<T, E>;
-    fn visit_call(&mut self, callee: &Expression, arguments: &[Expression]) -> Result<T, E>;
-    fn visit_grouping(&mut self, expression: &Expression) -> Result<T, E>;
-    fn visit_literal(&mut self, value: &Object) -> Result<T, E>;
+    ) -> Result<T, E> {
+        left.accept(self)?;
+        right.accept(self)?;
+        Ok(T::default())
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Expression,
+        _paren: &Token,
+        arguments: &[Expression],
+        named: &[(Token, Expression)],
+    ) -> Result<T, E> {
+        callee.accept(self)?;
+        for argument in arguments {
+            argument.accept(self)?;
+        }
+        for (_, argument) in named {
+            argument.accept(self)?;
+        }
+        Ok(T::default())
+    }
+
+    fn visit_conditional(
+        &mut self,
+        condition: &Expression,
+        then_branch: &Expression,
+        else_branch: &Expression,
+    ) -> Result<T, E> {
+        condition.accept(self)?;
+        then_branch.accept(self)?;
+        else_branch.accept(self)?;
+        Ok(T::default())
+    }
+
+    fn visit_get(&mut self, object: &Expression, _name: &Token) -> Result<T, E> {
+        object.accept(self)?;
+        Ok(T::default())
+    }
+
+    fn visit_grouping(&mut self, expression: &Expression) -> Result<T, E> {
+        expression.accept(self)?;
+        Ok(T::default())
+    }
+
+    fn visit_interpolation(&mut self, parts: &[Expression]) -> Result<T, E> {
+        for part in parts {
+            part.accept(self)?;
+        }
+        Ok(T::default())
+    }
+
+    fn visit_literal(&mut self, _value: &Object) -> Result<T, E> {
+        Ok(T::default())
+    }
+
     fn visit_logical(
         &mut self,
         left: &Expression,
-        operator: &Token,
+        _operator: &Token,
         right: &Expression,
-    ) -> Result<T, E>;
-    fn visit_unary(&mut self, operator: &Token, right: &Expression) -> Result<T, E>;
-    fn visit_variable(&mut self, name: &Token) -> Result<T, E>;
+    ) -> Result<T, E> {
+        left.accept(self)?;
+        right.accept(self)?;
+        Ok(T::default())
+    }
+
+    fn visit_unary(&mut self, _operator: &Token, right: &Expression) -> Result<T, E> {
+        right.accept(self)?;
+        Ok(T::default())
+    }
+
+    fn visit_variable(&mut self, _name: &Token) -> Result<T, E> {
+        Ok(T::default())
+    }
 }
@@ -0,0 +1,362 @@
+use std::rc::Rc;
+
+use serde_json::{json, Value};
+
+use crate::{
+    parser::{
+        expression::Expression,
+        object::Object,
+        statement::{Parameter, Statement},
+    },
+    token::{span::Span, Token},
+};
+
+use super::{expression_visitor::ExpressionVisitor, statement_visitor::StatementVisitor};
+
+// serializes the AST into JSON, every node carries its "kind" and "location"
+pub struct JsonPrinter;
+
+impl JsonPrinter {
+    pub fn print(&mut self, statements: &[Statement]) -> Value {
+        json!({
+            "kind": "Program",
+            "statements": self.statements(statements),
+        })
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Value {
+        let mut node = expression.accept(self).unwrap();
+        node["location"] = location(expression.line(), expression.span());
+        node
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Value {
+        let mut node = statement.accept(self).unwrap();
+        node["location"] = location(statement.line(), statement.span());
+        node
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Value {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect()
+    }
+}
+
+impl StatementVisitor<Value, String> for JsonPrinter {
+    fn visit_block_statement(&mut self, statements: &[Statement]) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Block",
+            "statements": self.statements(statements),
+        }))
+    }
+
+    fn visit_constant_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Expression,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Constant",
+            "name": identifier(name),
+            "initializer": self.expression(initializer),
+        }))
+    }
+
+    fn visit_expression_statement(&mut self, expr: &Expression) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Expression",
+            "expression": self.expression(expr),
+        }))
+    }
+
+    fn visit_function_statement(
+        &mut self,
+        name: &Token,
+        params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<Value, String> {
+        let names: Vec<Value> = params.iter().map(|param| identifier(&param.name)).collect();
+        // the defaults by parameter name, and the name of the rest parameter
+        let defaults: serde_json::Map<String, Value> = params
+            .iter()
+            .filter_map(|param| {
+                let default = param.default.as_ref()?;
+                Some((param.name.value.to_string(), self.expression(default)))
+            })
+            .collect();
+        let rest = params
+            .iter()
+            .find(|param| param.rest)
+            .map(|param| identifier(&param.name));
+        Ok(json!({
+            "kind": "Function",
+            "name": identifier(name),
+            "params": names,
+            "defaults": defaults,
+            "rest": rest,
+            "body": self.statements(body),
+        }))
+    }
+
+    fn visit_if_statement(
+        &mut self,
+        _keyword: &Token,
+        condition: &Expression,
+        then_branch: &Statement,
+        else_branch: &Option<Box<Statement>>,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "If",
+            "condition": self.expression(condition),
+            "then": self.statement(then_branch),
+            "else": else_branch.as_ref().map(|stmt| self.statement(stmt)),
+        }))
+    }
+
+    fn visit_import_statement(
+        &mut self,
+        path: &Token,
+        alias: &Option<Token>,
+        names: &[Token],
+    ) -> Result<Value, String> {
+        let names: Vec<Value> = names.iter().map(identifier).collect();
+        Ok(json!({
+            "kind": "Import",
+            "path": path.value.to_string(),
+            "alias": alias.as_ref().map(identifier),
+            "names": names,
+        }))
+    }
+
+    fn visit_print_statement(&mut self, expr: &Expression) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Print",
+            "expression": self.expression(expr),
+        }))
+    }
+
+    fn visit_return_statement(&mut self, value: &Option<Expression>) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Return",
+            "value": value.as_ref().map(|expr| self.expression(expr)),
+        }))
+    }
+
+    fn visit_variable_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expression>,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Variable",
+            "name": identifier(name),
+            "initializer": initializer.as_ref().map(|expr| self.expression(expr)),
+        }))
+    }
+
+    fn visit_while_statement(
+        &mut self,
+        _keyword: &Token,
+        condition: &Expression,
+        body: &Statement,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "While",
+            "condition": self.expression(condition),
+            "body": self.statement(body),
+        }))
+    }
+}
+
+impl ExpressionVisitor<Value, String> for JsonPrinter {
+    fn visit_assignment(&mut self, name: &Token, expression: &Expression) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Assignment",
+            "name": identifier(name),
+            "value": self.expression(expression),
+        }))
+    }
+
+    fn visit_binary(
+        &mut self,
+        left: &Expression,
+        operator: &Token,
+        right: &Expression,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Binary",
+            "operator": operator.token_type.to_string(),
+            "left": self.expression(left),
+            "right": self.expression(right),
+        }))
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Expression,
+        _paren: &Token,
+        arguments: &[Expression],
+        named: &[(Token, Expression)],
+    ) -> Result<Value, String> {
+        let arguments: Vec<Value> = arguments.iter().map(|arg| self.expression(arg)).collect();
+        let named: Vec<Value> = named
+            .iter()
+            .map(|(name, arg)| {
+                json!({
+                    "name": identifier(name),
+                    "value": self.expression(arg),
+                })
+            })
+            .collect();
+        Ok(json!({
+            "kind": "Call",
+            "callee": self.expression(callee),
+            "arguments": arguments,
+            "named": named,
+        }))
+    }
+
+    fn visit_conditional(
+        &mut self,
+        condition: &Expression,
+        then_branch: &Expression,
+        else_branch: &Expression,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Conditional",
+            "condition": self.expression(condition),
+            "then": self.expression(then_branch),
+            "else": self.expression(else_branch),
+        }))
+    }
+
+    fn visit_get(&mut self, object: &Expression, name: &Token) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Get",
+            "object": self.expression(object),
+            "name": identifier(name),
+        }))
+    }
+
+    fn visit_grouping(&mut self, expression: &Expression) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Grouping",
+            "expression": self.expression(expression),
+        }))
+    }
+
+    fn visit_interpolation(&mut self, parts: &[Expression]) -> Result<Value, String> {
+        let parts: Vec<Value> = parts.iter().map(|part| self.expression(part)).collect();
+        Ok(json!({
+            "kind": "Interpolation",
+            "parts": parts,
+        }))
+    }
+
+    fn visit_literal(&mut self, value: &Object) -> Result<Value, String> {
+        let value = match value {
+            Object::Integer(num) => json!(num),
+            Object::Number(num) => json!(num),
+            Object::String(str) => json!(str),
+            Object::Boolean(bool) => json!(bool),
+            Object::Callable(callable) => json!(callable.to_string()),
+            Object::Module(module) => json!(module.to_string()),
+            Object::List(_) => json!(value.to_string()),
+            Object::Nil => Value::Null,
+        };
+        Ok(json!({
+            "kind": "Literal",
+            "value": value,
+        }))
+    }
+
+    fn visit_logical(
+        &mut self,
+        left: &Expression,
+        operator: &Token,
+        right: &Expression,
+    ) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Logical",
+            "operator": operator.token_type.to_string(),
+            "left": self.expression(left),
+            "right": self.expression(right),
+        }))
+    }
+
+    fn visit_unary(&mut self, operator: &Token, right: &Expression) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Unary",
+            "operator": operator.token_type.to_string(),
+            "right": self.expression(right),
+        }))
+    }
+
+    fn visit_variable(&mut self, name: &Token) -> Result<Value, String> {
+        Ok(json!({
+            "kind": "Variable",
+            "name": identifier(name),
+        }))
+    }
+}
+
+fn identifier(token: &Token) -> Value {
+    json!(token.value.to_string())
+}
+
+// the line, column and source offsets of the node's token
+fn location(line: usize, span: Span) -> Value {
+    json!({
+        "line": line,
+        "column": span.column,
+        "start": span.start,
+        "end": span.end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser};
+
+    #[test]
+    fn test_locations() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new("var a = 1;\nprint a  + 2;"))
+            .parse()
+            .unwrap();
+        assert!(!errors.has_error());
+
+        let program = JsonPrinter.print(&statements);
+        let print = &program["statements"][1];
+        assert_eq!(
+            print["location"],
+            json!({ "line": 2, "column": 1, "start": 11, "end": 16 })
+        );
+        assert_eq!(
+            print["expression"]["location"],
+            json!({ "line": 2, "column": 10, "start": 20, "end": 21 })
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut errors = ErrorBag::new(Box::new(std::io::sink()));
+        let statements = Parser::new(&mut errors, Lexer::new("print \"a ${b}\";"))
+            .parse()
+            .unwrap();
+
+        let program = JsonPrinter.print(&statements);
+        let interpolation = &program["statements"][0]["expression"];
+        assert_eq!(interpolation["kind"], "Interpolation");
+        let kinds: Vec<&Value> = interpolation["parts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|part| &part["kind"])
+            .collect();
+        assert_eq!(kinds, ["Literal", "Variable", "Literal"]);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod expression_printer;
 pub mod expression_visitor;
+pub mod json_printer;
+pub mod statement_printer;
 pub mod statement_visitor;
@@ -0,0 +1,185 @@
+use std::rc::Rc;
+
+use crate::{
+    parser::{
+        expression::Expression,
+        statement::{Parameter, Statement},
+    },
+    token::Token,
+};
+
+use super::{expression_printer::ExpressionPrinter, statement_visitor::StatementVisitor};
+
+pub struct StatementPrinter;
+
+impl StatementPrinter {
+    pub fn print(&mut self, statement: &Statement) -> String {
+        statement.accept(self).unwrap()
+    }
+
+    fn print_all(&mut self, statements: &[Statement]) -> Result<String, String> {
+        let mut printed = Vec::new();
+        for statement in statements {
+            printed.push(statement.accept(self)?);
+        }
+        Ok(printed.join(" "))
+    }
+}
+
+impl StatementVisitor<String, String> for StatementPrinter {
+    fn visit_block_statement(&mut self, statements: &[Statement]) -> Result<String, String> {
+        Ok(format!("(block {})", self.print_all(statements)?))
+    }
+
+    fn visit_constant_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Expression,
+    ) -> Result<String, String> {
+        Ok(format!(
+            "(const {} {})",
+            name.value,
+            ExpressionPrinter.print(initializer)
+        ))
+    }
+
+    fn visit_expression_statement(&mut self, expr: &Expression) -> Result<String, String> {
+        Ok(ExpressionPrinter.print(expr))
+    }
+
+    fn visit_function_statement(
+        &mut self,
+        name: &Token,
+        params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<String, String> {
+        let params = params
+            .iter()
+            .map(|param| match (&param.default, param.rest) {
+                (Some(default), _) => {
+                    format!(
+                        "(= {} {})",
+                        param.name.value,
+                        ExpressionPrinter.print(default)
+                    )
+                }
+                (None, true) => format!("...{}", param.name.value),
+                (None, false) => param.name.value.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(format!(
+            "(fun {} ({}) {})",
+            name.value,
+            params,
+            self.print_all(body)?
+        ))
+    }
+
+    fn visit_if_statement(
+        &mut self,
+        _keyword: &Token,
+        condition: &Expression,
+        then_branch: &Statement,
+        else_branch: &Option<Box<Statement>>,
+    ) -> Result<String, String> {
+        let condition = ExpressionPrinter.print(condition);
+        let then_branch = then_branch.accept(self)?;
+        match else_branch {
+            Some(else_branch) => {
+                let else_branch = else_branch.accept(self)?;
+                Ok(format!(
+                    "(if {} {} {})",
+                    condition, then_branch, else_branch
+                ))
+            }
+            None => Ok(format!("(if {} {})", condition, then_branch)),
+        }
+    }
+
+    fn visit_import_statement(
+        &mut self,
+        path: &Token,
+        alias: &Option<Token>,
+        names: &[Token],
+    ) -> Result<String, String> {
+        match alias {
+            Some(alias) => Ok(format!("(import \"{}\" {})", path.value, alias.value)),
+            None => {
+                let names = names
+                    .iter()
+                    .map(|name| name.value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Ok(format!("(from \"{}\" {})", path.value, names))
+            }
+        }
+    }
+
+    fn visit_print_statement(&mut self, expr: &Expression) -> Result<String, String> {
+        Ok(format!("(print {})", ExpressionPrinter.print(expr)))
+    }
+
+    fn visit_return_statement(&mut self, value: &Option<Expression>) -> Result<String, String> {
+        match value {
+            Some(value) => Ok(format!("(return {})", ExpressionPrinter.print(value))),
+            None => Ok("(return)".to_string()),
+        }
+    }
+
+    fn visit_variable_statement(
+        &mut self,
+        name: &Token,
+        initializer: &Option<Expression>,
+    ) -> Result<String, String> {
+        match initializer {
+            Some(initializer) => Ok(format!(
+                "(var {} {})",
+                name.value,
+                ExpressionPrinter.print(initializer)
+            )),
+            None => Ok(format!("(var {})", name.value)),
+        }
+    }
+
+    fn visit_while_statement(
+        &mut self,
+        _keyword: &Token,
+        condition: &Expression,
+        body: &Statement,
+    ) -> Result<String, String> {
+        let condition = ExpressionPrinter.print(condition);
+        Ok(format!("(while {} {})", condition, body.accept(self)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_bag::ErrorBag, lexer::Lexer, parser::Parser};
+    use rstest::*;
+
+    #[rstest]
+    #[case("1 + 2;", "(+ 1 2)")]
+    #[case("var a = 1;", "(var a 1)")]
+    #[case("var a;", "(var a)")]
+    #[case("const a = 1;", "(const a 1)")]
+    #[case("print a and b;", "(print (and a b))")]
+    #[case("fun f(a, b) { return a; }", "(fun f (a b) (return a))")]
+    #[case("fun f() { return; }", "(fun f () (return))")]
+    #[case("fun f(a, b = a + 1, ...c) {}", "(fun f (a (= b (+ a 1)) ...c) )")]
+    #[case("f(1, b: c ? 2 : 3);", "(call f 1 (: b (?: c 2 3)))")]
+    #[case("if (a) print 1; else print 2;", "(if a (print 1) (print 2))")]
+    #[case("while (a) { a = f(1, 2); }", "(while a (block (set a (call f 1 2))))")]
+    #[case("import \"lib/math.lox\" as math;", "(import \"lib/math.lox\" math)")]
+    #[case("from \"math.lox\" import pi, tau;", "(from \"math.lox\" pi tau)")]
+    #[case("print math.sqrt(2).x;", "(print (. (call (. math sqrt) 2) x))")]
+    fn test_statement_print(#[case] source: &str, #[case] expected: &str) {
+        let mut errors = ErrorBag::default();
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        assert_eq!(StatementPrinter.print(&statements[0]), expected);
+    }
+}
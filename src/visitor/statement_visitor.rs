@@ -1,46 +1,157 @@
+use std::rc::Rc;
+
 use crate::{
-    interpreter::interpreter_error::InterpreterError,
-    parser::{expression::Expression, object::Object, statement::Statement},
+    parser::{
+        expression::Expression,
+        statement::{Parameter, Statement},
+    },
     token::Token,
 };
 
-pub trait StatementVisitor {
-    fn visit_block_statement(
-        &mut self,
-        statements: &[Statement],
-    ) -> Result<Option<Object>, InterpreterError>;
-    fn visit_expression_statement(
+// The default implementations walk the whole tree and return `T::default()`,
+// so passes only need to override the statements they care about. Expressions
+// reached by the walk are handed to `visit_expression`, which does nothing
+// unless the pass overrides it.
+pub trait StatementVisitor<T: Default, E> {
+    fn visit_expression(&mut self, _expression: &Expression) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_block_statement(&mut self, statements: &[Statement]) -> Result<T, E> {
+        for statement in statements {
+            statement.accept(self)?;
+        }
+        Ok(T::default())
+    }
+
+    fn visit_constant_statement(
         &mut self,
-        expr: &Expression,
-    ) -> Result<Option<Object>, InterpreterError>;
+        _name: &Token,
+        initializer: &Expression,
+    ) -> Result<T, E> {
+        self.visit_expression(initializer)?;
+        Ok(T::default())
+    }
+
+    fn visit_expression_statement(&mut self, expr: &Expression) -> Result<T, E> {
+        self.visit_expression(expr)?;
+        Ok(T::default())
+    }
+
     fn visit_function_statement(
         &mut self,
-        name: &Token,
-        params: &[Token],
-        body: &[Statement],
-    ) -> Result<Option<Object>, InterpreterError>;
+        _name: &Token,
+        params: &Rc<[Parameter]>,
+        body: &Rc<[Statement]>,
+    ) -> Result<T, E> {
+        for default in params.iter().filter_map(|param| param.default.as_ref()) {
+            self.visit_expression(default)?;
+        }
+        for statement in body.iter() {
+            statement.accept(self)?;
+        }
+        Ok(T::default())
+    }
+
     fn visit_if_statement(
         &mut self,
+        _keyword: &Token,
         condition: &Expression,
         then_branch: &Statement,
         else_branch: &Option<Box<Statement>>,
-    ) -> Result<Option<Object>, InterpreterError>;
-    fn visit_print_statement(
-        &mut self,
-        print: &Expression,
-    ) -> Result<Option<Object>, InterpreterError>;
-    fn visit_return_statement(
+    ) -> Result<T, E> {
+        self.visit_expression(condition)?;
+        then_branch.accept(self)?;
+        if let Some(else_branch) = else_branch {
+            else_branch.accept(self)?;
+        }
+        Ok(T::default())
+    }
+
+    fn visit_import_statement(
         &mut self,
-        value: &Option<Expression>,
-    ) -> Result<Option<Object>, InterpreterError>;
+        _path: &Token,
+        _alias: &Option<Token>,
+        _names: &[Token],
+    ) -> Result<T, E> {
+        Ok(T::default())
+    }
+
+    fn visit_print_statement(&mut self, print: &Expression) -> Result<T, E> {
+        self.visit_expression(print)?;
+        Ok(T::default())
+    }
+
+    fn visit_return_statement(&mut self, value: &Option<Expression>) -> Result<T, E> {
+        if let Some(value) = value {
+            self.visit_expression(value)?;
+        }
+        Ok(T::default())
+    }
+
     fn visit_variable_statement(
         &mut self,
-        name: &Token,
+        _name: &Token,
         initializer: &Option<Expression>,
-    ) -> Result<Option<Object>, InterpreterError>;
+    ) -> Result<T, E> {
+        if let Some(initializer) = initializer {
+            self.visit_expression(initializer)?;
+        }
+        Ok(T::default())
+    }
+
     fn visit_while_statement(
         &mut self,
+        _keyword: &Token,
         condition: &Expression,
         body: &Statement,
-    ) -> Result<Option<Object>, InterpreterError>;
+    ) -> Result<T, E> {
+        self.visit_expression(condition)?;
+        body.accept(self)?;
+        Ok(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error_bag::ErrorBag, lexer::Lexer, parser::Parser,
+        visitor::expression_visitor::ExpressionVisitor,
+    };
+
+    // only overrides the hooks it needs, everything else is walked by default
+    #[derive(Default)]
+    struct VariableCounter {
+        count: usize,
+    }
+
+    impl ExpressionVisitor<(), ()> for VariableCounter {
+        fn visit_variable(&mut self, _name: &Token) -> Result<(), ()> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    impl StatementVisitor<(), ()> for VariableCounter {
+        fn visit_expression(&mut self, expression: &Expression) -> Result<(), ()> {
+            expression.accept(self)
+        }
+    }
+
+    #[test]
+    fn test_default_walk() {
+        let source = "fun f(a) { if (a) { return g(a, b); } } while (c) print -d or e;";
+        let mut errors = ErrorBag::default();
+        let statements = Parser::new(&mut errors, Lexer::new(source))
+            .parse()
+            .unwrap();
+
+        let mut counter = VariableCounter::default();
+        for statement in &statements {
+            statement.accept(&mut counter).unwrap();
+        }
+
+        assert_eq!(counter.count, 7);
+    }
 }